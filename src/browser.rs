@@ -0,0 +1,246 @@
+use crate::error::Error;
+use crate::library;
+use crate::metadata::Metadata;
+use crate::puzzle;
+use crate::tty::read_event;
+use crate::GameOfLife;
+
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
+use crossterm::style::{self, Print, Stylize};
+use crossterm::terminal::{self, Clear, ClearType};
+use crossterm::{cursor, execute, queue};
+
+use std::collections::HashMap;
+use std::io::Stdout;
+use std::path::{Path, PathBuf};
+
+//lines of text above the list
+const HEADER: u16 = 4;
+//columns used by the list, the preview takes the rest of the terminal
+const LIST_WIDTH: u16 = 36;
+
+struct Entry {
+    name: String,
+    path: PathBuf,
+    is_dir: bool,
+    //the error is shown in the preview
+    metadata: Result<Metadata, String>,
+}
+impl Entry {
+    //the filter is looked for in the file name, and in the name and tags of the pattern
+    fn matches(&self, filter: &str) -> bool {
+        self.name.to_lowercase().contains(&filter.to_lowercase())
+            || self.metadata.as_ref().is_ok_and(|m| m.matches(filter))
+    }
+}
+
+//files and subdirectories of dir, directories first, hidden and metadata files left out
+fn read_entries(dir: &Path) -> Result<Vec<Entry>, Error> {
+    let mut entries: Vec<Entry> = library::read_dir(dir)?
+        .into_iter()
+        .filter(|p| p.extension().is_none_or(|ext| ext != "meta"))
+        .map(|p| Entry {
+            name: p.file_name().unwrap().to_string_lossy().to_string(),
+            is_dir: library::is_dir(&p),
+            metadata: match library::is_dir(&p) {
+                true => Ok(Metadata::default()),
+                false => Metadata::read(&p.to_string_lossy()).map_err(|e| e.to_string()),
+            },
+            path: p,
+        })
+        .filter(|e| !e.name.starts_with('.'))
+        .collect();
+    entries.sort_by(|a, b| b.is_dir.cmp(&a.is_dir).then_with(|| a.name.cmp(&b.name)));
+    Ok(entries)
+}
+
+//levels are loaded with their goal, every other file like the patterns given to the menu
+fn load(path: &str) -> Result<GameOfLife, Error> {
+    if path.ends_with(".level") {
+        puzzle::load(path)
+    } else {
+        GameOfLife::init(path)
+    }
+}
+
+//the pattern shrunk to fit in width x height characters, a character being alive when one of the
+//cells it covers is
+fn thumbnail(game: &GameOfLife, width: usize, height: usize) -> Vec<String> {
+    let coords = &game.game_grid.alive_cells.coords;
+    if coords.is_empty() || width == 0 || height == 0 {
+        return vec![];
+    }
+    let min_x = coords.iter().map(|c| c.0).min().unwrap();
+    let min_y = coords.iter().map(|c| c.1).min().unwrap();
+    let w = (coords.iter().map(|c| c.0).max().unwrap() - min_x + 1) as usize;
+    let h = (coords.iter().map(|c| c.1).max().unwrap() - min_y + 1) as usize;
+    let scale = w.div_ceil(width).max(h.div_ceil(height));
+
+    let mut lines = vec![vec![' '; w.div_ceil(scale)]; h.div_ceil(scale)];
+    for (x, y) in coords.iter() {
+        let i = (x - min_x) as usize / scale;
+        let j = (y - min_y) as usize / scale;
+        lines[j][i] = '█';
+    }
+    lines.into_iter().map(|l| l.into_iter().collect()).collect()
+}
+
+//scrollable list of the patterns below base_dir: arrows to move, letters to filter by name,
+//enter to open a directory or choose a file. Returns None when the user goes back to the menu.
+//The library's directory must be readable, the errors of the subdirectories are shown above
+//the list
+#[allow(unused_must_use)]
+pub fn browse(so: &mut Stdout, base_dir: &str) -> Result<Option<String>, Error> {
+    let base = PathBuf::from(base_dir);
+    let mut dir = base.clone();
+    let mut entries = read_entries(&dir)?;
+    //error of the last directory opened
+    let mut error: Option<Error> = None;
+    let mut filter = String::new();
+    let mut selected = 0;
+    //first entry shown
+    let mut top = 0;
+    //patterns are read again only when the highlighted one changes
+    let mut previews: HashMap<PathBuf, Result<GameOfLife, String>> = HashMap::new();
+
+    loop {
+        let shown: Vec<&Entry> = entries
+            .iter()
+            .filter(|e| e.matches(&filter))
+            .collect();
+        selected = selected.min(shown.len().saturating_sub(1));
+        let size = terminal::size().map_err(Error::Terminal)?;
+        let rows = size.1.saturating_sub(HEADER + 1).max(1) as usize;
+        if selected < top {
+            top = selected;
+        } else if selected >= top + rows {
+            top = selected + 1 - rows;
+        }
+
+        queue!(so, Clear(ClearType::All), cursor::MoveTo(0, 0));
+        queue!(
+            so,
+            Print("Choose a file to load (arrows to move; type to filter by name or tag; enter to open; esc to go back)"),
+            cursor::MoveToNextLine(1),
+            Print(format!("Directory: {}", dir.display())),
+            cursor::MoveToNextLine(1),
+            Print(format!("Filter: {}", filter))
+        );
+        if let Some(e) = error.take() {
+            queue!(so, cursor::MoveToNextLine(1), Print(format!("Error: {}", e)));
+        }
+        for (i, e) in shown.iter().enumerate().skip(top).take(rows) {
+            let mut name = e.name.clone();
+            if e.is_dir {
+                name.push('/');
+            }
+            let name: String = name.chars().take(LIST_WIDTH as usize - 3).collect();
+            queue!(so, cursor::MoveTo(0, HEADER + (i - top) as u16));
+            if i == selected {
+                queue!(so, style::PrintStyledContent(format!("> {}", name).black().on_white()));
+            } else {
+                queue!(so, Print(format!("  {}", name)));
+            }
+        }
+        if shown.is_empty() {
+            queue!(so, cursor::MoveTo(0, HEADER), Print("  no pattern matches"));
+        }
+
+        //preview of the highlighted file
+        if let Some(e) = shown.get(selected).filter(|e| !e.is_dir) {
+            let preview = previews
+                .entry(e.path.clone())
+                .or_insert_with(|| load(&e.path.to_string_lossy()).map_err(|err| err.to_string()));
+            let width = size.0.saturating_sub(LIST_WIDTH) as usize;
+            //information about the pattern first, then the thumbnail below it
+            let mut lines = vec![];
+            match &e.metadata {
+                Ok(m) => {
+                    lines.extend(m.summary());
+                    if let Some(rule) = &m.rule {
+                        lines.push(format!("Rule: {}", rule));
+                    }
+                    if !m.tags.is_empty() {
+                        lines.push(format!("Tags: {}", m.tags.join(", ")));
+                    }
+                    lines.extend(m.description.iter().cloned());
+                }
+                Err(err) => lines.push(err.clone()),
+            }
+            match preview {
+                Ok(game) => lines.push(format!(
+                    "{} cells; Rule: {}",
+                    game.game_grid.alive_cells.coords.len(),
+                    game.rule.name
+                )),
+                Err(err) => lines.push(format!("cannot be read: {}", err)),
+            }
+            for (j, line) in lines.iter().enumerate() {
+                let line: String = line.chars().take(width).collect();
+                queue!(so, cursor::MoveTo(LIST_WIDTH, HEADER + j as u16), Print(line));
+            }
+            if let Ok(game) = preview {
+                let top = lines.len() + 1;
+                let thumbnail = thumbnail(game, width, rows.saturating_sub(top));
+                for (j, line) in thumbnail.iter().enumerate() {
+                    queue!(
+                        so,
+                        cursor::MoveTo(LIST_WIDTH, HEADER + (top + j) as u16),
+                        style::PrintStyledContent(line.as_str().dark_cyan())
+                    );
+                }
+            }
+        }
+        execute!(so);
+        let count = shown.len();
+        let current = shown.get(selected).map(|e| (e.path.clone(), e.is_dir));
+
+        let (code, modifiers) = match read_event()? {
+            Event::Key(KeyEvent { code, modifiers }) => (code, modifiers),
+            _ => continue,
+        };
+        match code {
+            KeyCode::Up => selected = selected.saturating_sub(1),
+            KeyCode::Down => selected += 1,
+            KeyCode::PageUp => selected = selected.saturating_sub(rows),
+            KeyCode::PageDown => selected += rows,
+            KeyCode::Home => selected = 0,
+            KeyCode::End => selected = count,
+            KeyCode::Enter => match current {
+                Some((path, true)) => match read_entries(&path) {
+                    Ok(e) => {
+                        dir = path;
+                        entries = e;
+                        filter.clear();
+                        selected = 0;
+                    }
+                    Err(e) => error = Some(e),
+                },
+                Some((path, false)) => return Ok(Some(path.to_string_lossy().to_string())),
+                None => (),
+            },
+            //going up stops at the library's directory
+            KeyCode::Left | KeyCode::Backspace if filter.is_empty() && dir != base => {
+                let mut parent = dir.clone();
+                parent.pop();
+                match read_entries(&parent) {
+                    Ok(e) => {
+                        selected = e.iter().position(|e| e.path == dir).unwrap_or(0);
+                        entries = e;
+                        dir = parent;
+                    }
+                    Err(e) => error = Some(e),
+                }
+            }
+            KeyCode::Backspace => {
+                filter.pop();
+            }
+            KeyCode::Esc => return Ok(None),
+            KeyCode::Char(c) if modifiers == KeyModifiers::NONE || modifiers == KeyModifiers::SHIFT => {
+                filter.push(c);
+                selected = 0;
+            }
+            _ => (),
+        }
+    }
+}
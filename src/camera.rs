@@ -11,28 +11,41 @@ const X_INIT_RATIO: f64 = 0.8;
 const Y_INIT_RATIO: f64 = 0.8;
 impl ViewRect {
     pub fn new(x: isize, y: isize, x_max: isize, y_max: isize) -> Self {
-        let x_len = (x_max as f64 * X_INIT_RATIO) as isize;
-        let y_len = (y_max as f64 * Y_INIT_RATIO) as isize;
+        Self::with_ratios(x, y, x_max, y_max, X_INIT_RATIO, Y_INIT_RATIO)
+    }
+    //the ratios are the part of the terminal taken at the start
+    pub fn with_ratios(x: isize, y: isize, x_max: isize, y_max: isize, x_ratio: f64, y_ratio: f64) -> Self {
+        let x_len = (x_max as f64 * x_ratio) as isize;
+        let y_len = (y_max as f64 * y_ratio) as isize;
         Self {
-            x: x,
-            y: y,
-            x_len: x_len,
-            y_len: y_len,
-            x_max: x_max,
-            y_max: y_max,
+            x,
+            y,
+            x_len,
+            y_len,
+            x_max,
+            y_max,
         }
     }
 
+    //the terminal is columns x rows with lines of text above the grid, which has a border
+    pub fn fit(&mut self, columns: u16, rows: u16, text_lines: u16) {
+        self.x_max = (columns as isize - 2).max(1);
+        self.y_max = (rows as isize - text_lines as isize - 2).max(1);
+        self.x_len = self.x_len.min(self.x_max);
+        self.y_len = self.y_len.min(self.y_max);
+    }
+
     pub fn zoom_x(&mut self, amount: isize) {
         let future_len = self.x_len - 2*amount;
-        if amount > 0 || (amount < 0 && future_len <= self.x_max) {
+        //at least one cell stays visible
+        if (amount > 0 && future_len >= 1) || (amount < 0 && future_len <= self.x_max) {
             self.x += amount;
             self.x_len = future_len;
         }
     }
     pub fn zoom_y(&mut self, amount: isize) {
         let future_len = self.y_len - 2*amount;
-        if amount > 0 || (amount < 0 && future_len <= self.y_max) {
+        if (amount > 0 && future_len >= 1) || (amount < 0 && future_len <= self.y_max) {
             self.y += amount;
             self.y_len = future_len;
         }
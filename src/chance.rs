@@ -0,0 +1,81 @@
+use crate::error::Error;
+use crate::rule::Rule;
+use crate::{GameGrid, GameOfLife};
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use std::time::SystemTime;
+
+//probabilities for stochastic rules, the same seed always gives the same run
+#[derive(Clone)]
+pub struct Chance {
+    //probability that a birth given by the rule happens
+    pub birth: f64,
+    //probability that a cell kept alive by the rule survives
+    pub survival: f64,
+    //probability for each cell of the grid to be flipped every generation
+    pub noise: f64,
+    pub seed: u64,
+    rng: ChaCha8Rng,
+}
+impl Chance {
+    pub fn new(birth: f64, survival: f64, noise: f64, seed: u64) -> Result<Chance, Error> {
+        for (name, p) in [("birth", birth), ("survival", survival), ("noise", noise)].iter() {
+            if !(0.0..=1.0).contains(p) {
+                return Err(Error::Invalid(format!(
+                    "the {} probability must be between 0 and 1, not {}",
+                    name, p
+                )));
+            }
+        }
+
+        Ok(Chance {
+            birth,
+            survival,
+            noise,
+            seed,
+            rng: ChaCha8Rng::seed_from_u64(seed),
+        })
+    }
+
+    pub fn deterministic() -> Chance {
+        Chance::new(1.0, 1.0, 0.0, random_seed()).unwrap()
+    }
+
+    pub fn is_deterministic(&self) -> bool {
+        self.birth >= 1.0 && self.survival >= 1.0 && self.noise <= 0.0
+    }
+
+    //certain events don't use the generator so deterministic runs don't depend on the seed
+    pub fn happens(&mut self, probability: f64) -> bool {
+        probability >= 1.0 || probability > 0.0 && self.rng.gen_bool(probability)
+    }
+}
+
+//used when no seed is given, it is shown in the hud so the run can be reproduced
+pub fn random_seed() -> u64 {
+    //a clock set before 1970 gives the seed 0
+    let now = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default();
+    now.as_nanos() as u64 % 1_000_000
+}
+
+impl GameOfLife {
+    //every cell of the width x height rectangle is alive with the probability density
+    pub fn random(width: isize, height: isize, density: f64, seed: u64) -> GameOfLife {
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        let mut g = GameGrid::new();
+        //the whole rectangle is in the grid, even if its last cells are dead
+        g.set_cell(width - 1, height - 1, false);
+        for y in 0..height {
+            for x in 0..width {
+                if rng.gen_bool(density) {
+                    g.set_cell(x, y, true);
+                }
+            }
+        }
+        GameOfLife::new(g, Rule::conway())
+    }
+}
@@ -0,0 +1,178 @@
+use crate::chance::{self, Chance};
+use crate::config::{self, Config};
+use crate::error::Error;
+use crate::font::{Font, Layout};
+use crate::library::Library;
+use crate::rule::Rule;
+use crate::topology::Topology;
+use crate::transform::{self, Transform};
+
+use std::io::{self, Read};
+use std::str::FromStr;
+
+//options given on the command line
+pub struct Options {
+    pub chance: Chance,
+    //font used to draw words
+    pub font: Font,
+    pub layout: Layout,
+    //applied in order to the pattern before it starts
+    pub transforms: Vec<Transform>,
+    pub library: Library,
+    pub config: Config,
+    //pattern the game starts from without the menu
+    pub start: Option<Start>,
+    //replaces the rule of the pattern
+    pub rule: Option<Rule>,
+    //generations per second, the configuration's speed by default
+    pub speed: Option<f32>,
+    pub topology: Topology,
+    pub paused: bool,
+}
+
+pub enum Start {
+    //path of a pattern, given after `view`
+    File(String),
+    //pattern read from the standard input, given as "-" after `view`
+    Stdin(String),
+    Word(String),
+    //cells of a width x height rectangle are alive with the probability density
+    Random {
+        width: isize,
+        height: isize,
+        density: f64,
+        seed: u64,
+    },
+}
+
+//"<width>x<height>:<density>" with an optional ":<seed>"
+fn parse_random(s: &str) -> Option<Start> {
+    let mut parts = s.split(':');
+    let (width, height) = parts.next()?.split_once('x')?;
+    let width = width.trim().parse().ok().filter(|w| *w > 0)?;
+    let height = height.trim().parse().ok().filter(|h| *h > 0)?;
+    let density = parts.next()?.trim().parse().ok().filter(|d| (0.0..=1.0).contains(d))?;
+    let seed = match parts.next() {
+        Some(seed) => seed.trim().parse().ok()?,
+        None => chance::random_seed(),
+    };
+    if parts.next().is_some() {
+        return None;
+    }
+    Some(Start::Random {
+        width,
+        height,
+        density,
+        seed,
+    })
+}
+
+pub fn parse(args: &[String]) -> Result<Options, Error> {
+    let mut birth = 1.0;
+    let mut survival = 1.0;
+    let mut noise = 0.0;
+    let mut seed = None;
+    let mut font = None;
+    let mut font_scale = 1;
+    let mut layout = Layout::new();
+    let mut transforms = vec![];
+    let mut starts = vec![];
+    let mut rule = None;
+    let mut speed = None;
+    let mut topology = Topology::Plane;
+    let mut paused = false;
+
+    //`view` skips the menu and takes the path of the pattern
+    let view = args.first().map(String::as_str) == Some("view");
+    let mut i = view as usize;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--birth-probability" => birth = value(args, &mut i)?,
+            "--survival-probability" => survival = value(args, &mut i)?,
+            "--noise" => noise = value(args, &mut i)?,
+            "--seed" => seed = Some(value(args, &mut i)?),
+            "--font" => font = Some(value::<String>(args, &mut i)?),
+            "--font-scale" => font_scale = value(args, &mut i)?,
+            "--letter-spacing" => layout.letter_spacing = value(args, &mut i)?,
+            "--line-spacing" => layout.line_spacing = value(args, &mut i)?,
+            "--align" => layout.align = value(args, &mut i)?,
+            "--transform" => transforms.push(value(args, &mut i)?),
+            "--rule" => rule = Some(Rule::parse(&value::<String>(args, &mut i)?)?),
+            "--speed" => speed = Some(value(args, &mut i)?),
+            "--topology" => topology = value(args, &mut i)?,
+            "--start-paused" => paused = true,
+            "--word" => starts.push(Start::Word(value(args, &mut i)?)),
+            "--random" => {
+                let spec: String = value(args, &mut i)?;
+                let start = parse_random(&spec).ok_or_else(|| {
+                    invalid_argument(&format!("invalid value '{}' for --random", spec))
+                })?;
+                if let Start::Random { width, height, .. } = start {
+                    transform::check_size(width as usize, height as usize)?;
+                }
+                starts.push(start);
+            }
+            //read before the terminal is put in raw mode
+            "-" if view => {
+                let mut contents = String::new();
+                io::stdin().read_to_string(&mut contents)?;
+                starts.push(Start::Stdin(contents));
+            }
+            a if view && !a.starts_with("--") => starts.push(Start::File(a.to_string())),
+            a => return Err(invalid_argument(&format!("unknown argument '{}'", a))),
+        }
+        i += 1;
+    }
+
+    if starts.len() > 1 {
+        return Err(invalid_argument(
+            "only one of a pattern, --word and --random can be given",
+        ));
+    }
+    if view && starts.is_empty() {
+        return Err(invalid_argument("view needs a pattern, --word or --random"));
+    }
+    if speed.is_some_and(|s: f32| s <= 0.0) {
+        return Err(invalid_argument("the speed must be more than 0"));
+    }
+    if font_scale == 0 {
+        return Err(invalid_argument("the font scale must be at least 1"));
+    }
+    let config = config::load()?;
+    let library = Library::new(&config);
+    let font = match font {
+        Some(name) => Font::load(&library.font_path(&name).to_string_lossy(), font_scale)?,
+        None => Font::builtin(font_scale),
+    };
+
+    let seed = seed.unwrap_or_else(chance::random_seed);
+    Ok(Options {
+        chance: Chance::new(birth, survival, noise, seed)?,
+        font,
+        layout,
+        transforms,
+        library,
+        config,
+        start: starts.pop(),
+        rule,
+        speed,
+        topology,
+        paused,
+    })
+}
+
+//value following the argument at i, i is moved to it
+fn value<T: FromStr>(args: &[String], i: &mut usize) -> Result<T, Error> {
+    let name = &args[*i];
+    *i += 1;
+    match args.get(*i) {
+        Some(v) => v
+            .parse()
+            .map_err(|_| invalid_argument(&format!("invalid value '{}' for {}", v, name))),
+        None => Err(invalid_argument(&format!("missing value for {}", name))),
+    }
+}
+
+fn invalid_argument(reason: &str) -> Error {
+    Error::invalid(reason)
+}
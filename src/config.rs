@@ -0,0 +1,237 @@
+use crate::error::Error;
+use crate::keys::{self, Keys};
+use crate::theme::{self, Theme};
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+use toml::Value;
+
+//written by --write-config, with the default values
+const DEFAULT_CONFIG: &str = r##"# directories searched for patterns, levels and fonts before the default ones
+[paths]
+patterns = []
+levels = []
+fonts = []
+
+# keys used while the pattern plays: a single character or up, down, left, right, space, enter,
+# esc, tab, backspace, delete, pageup or pagedown
+[keys]
+quit = "q"
+speed_up = "x"
+slow_down = "c"
+up = "up"
+down = "down"
+left = "left"
+right = "right"
+zoom = "z"
+unzoom = "u"
+reverse = "b"
+edit = "e"
+rotate = "r"
+flip_horizontal = "h"
+flip_vertical = "v"
+transpose = "t"
+scale = "m"
+pause = "p"
+
+[defaults]
+# generations per second
+speed = 4.0
+# cells the camera moves by
+move = 2
+# part of the terminal taken by the grid at the start
+view_width = 0.8
+view_height = 0.8
+
+# colours are names (black, dark_grey, red, dark_red, green, dark_green, yellow, dark_yellow, blue,
+# dark_blue, magenta, dark_magenta, cyan, dark_cyan, white, grey) or "#rrggbb"
+[theme]
+# alive cells, one for each colour of multi-colour rules
+alive = ["dark_cyan", "dark_yellow", "red", "green"]
+dead = "magenta"
+outside = "dark_red"
+border = "dark_green"
+"##;
+
+//settings read from the configuration file, $GAME_OF_LIFE_CONFIG or
+//$XDG_CONFIG_HOME/game-of-life/config.toml (~/.config/game-of-life/config.toml by default)
+pub struct Config {
+    //[paths] section: directories searched before the default ones
+    pub pattern_dirs: Vec<PathBuf>,
+    pub level_dirs: Vec<PathBuf>,
+    pub font_dirs: Vec<PathBuf>,
+    pub keys: Keys,
+    //[defaults] section
+    pub speed: f32,
+    pub step: isize,
+    pub view_width: f64,
+    pub view_height: f64,
+    pub theme: Theme,
+}
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            pattern_dirs: vec![],
+            level_dirs: vec![],
+            font_dirs: vec![],
+            keys: Keys::default(),
+            speed: 4.0,
+            step: 2,
+            view_width: 0.8,
+            view_height: 0.8,
+            theme: Theme::default(),
+        }
+    }
+}
+
+pub fn path() -> Option<PathBuf> {
+    if let Some(p) = env::var_os("GAME_OF_LIFE_CONFIG") {
+        return Some(PathBuf::from(p));
+    }
+    let config_home = match env::var_os("XDG_CONFIG_HOME") {
+        Some(d) if !d.is_empty() => PathBuf::from(d),
+        _ => home()?.join(".config"),
+    };
+    Some(config_home.join("game-of-life").join("config.toml"))
+}
+
+pub fn home() -> Option<PathBuf> {
+    env::var_os("HOME").filter(|h| !h.is_empty()).map(PathBuf::from)
+}
+
+//a missing file gives the default configuration
+pub fn load() -> Result<Config, Error> {
+    let path = match path() {
+        Some(p) if p.is_file() => p,
+        _ => return Ok(Config::default()),
+    };
+    let contents = fs::read_to_string(&path)?;
+    parse(&contents).map_err(|reason| Error::Invalid(format!("{}: {}", path.display(), reason)))
+}
+
+//the existing file is never replaced
+pub fn write_default() -> Result<PathBuf, Error> {
+    let path = path().ok_or_else(|| {
+        Error::invalid("no configuration directory, set $HOME or $GAME_OF_LIFE_CONFIG")
+    })?;
+    if path.exists() {
+        return Err(Error::Invalid(format!("{} already exists", path.display())));
+    }
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(&path, DEFAULT_CONFIG)?;
+    Ok(path)
+}
+
+fn parse(contents: &str) -> Result<Config, String> {
+    let value: Value = contents.parse().map_err(|e| format!("{}", e))?;
+    let mut config = Config::default();
+
+    for (section, settings) in table(&value, "the file")? {
+        if !["paths", "keys", "defaults", "theme"].contains(&section.as_str()) {
+            return Err(format!("unknown section [{}]", section));
+        }
+        for (key, v) in table(settings, section)? {
+            let name = format!("{}.{}", section, key);
+            match (section.as_str(), key.as_str()) {
+                ("paths", "patterns") => config.pattern_dirs = dirs(v, &name)?,
+                ("paths", "levels") => config.level_dirs = dirs(v, &name)?,
+                ("paths", "fonts") => config.font_dirs = dirs(v, &name)?,
+                ("keys", k) => {
+                    let action = keys::ACTIONS
+                        .iter()
+                        .find(|a| a.0 == k)
+                        .ok_or_else(|| format!("unknown setting {}", name))?
+                        .1;
+                    let code = keys::parse_key(string(v, &name)?)
+                        .map_err(|e| format!("{}: {}", name, e))?;
+                    config.keys.set(action, code);
+                }
+                ("defaults", "speed") => {
+                    config.speed = number(v, &name)? as f32;
+                    if config.speed <= 0.0 {
+                        return Err(format!("{} must be more than 0", name));
+                    }
+                }
+                ("defaults", "move") => {
+                    config.step = v
+                        .as_integer()
+                        .filter(|n| *n > 0)
+                        .ok_or_else(|| format!("{} must be a whole number more than 0", name))?
+                        as isize
+                }
+                ("defaults", "view_width") => config.view_width = ratio(v, &name)?,
+                ("defaults", "view_height") => config.view_height = ratio(v, &name)?,
+                ("theme", "alive") => {
+                    let list = v
+                        .as_array()
+                        .filter(|l| !l.is_empty() && l.len() <= 4)
+                        .ok_or_else(|| format!("{} must be a list of 1 to 4 colours", name))?;
+                    //missing colours of multi-colour rules keep their default
+                    for (i, c) in list.iter().enumerate() {
+                        config.theme.colours[i] = colour(c, &name)?;
+                    }
+                }
+                ("theme", "dead") => config.theme.dead = colour(v, &name)?,
+                ("theme", "outside") => config.theme.outside = colour(v, &name)?,
+                ("theme", "border") => config.theme.border = colour(v, &name)?,
+                _ => return Err(format!("unknown setting {}", name)),
+            }
+        }
+    }
+
+    if let Some((a, b)) = config.keys.duplicate() {
+        return Err(format!(
+            "keys.{} and keys.{} use the same key",
+            keys::action_name(a),
+            keys::action_name(b)
+        ));
+    }
+    Ok(config)
+}
+
+fn table<'a>(v: &'a Value, name: &str) -> Result<&'a toml::value::Table, String> {
+    v.as_table()
+        .ok_or_else(|| format!("{} must be a section", name))
+}
+
+fn string<'a>(v: &'a Value, name: &str) -> Result<&'a str, String> {
+    v.as_str().ok_or_else(|| format!("{} must be a string", name))
+}
+
+//integers are accepted where decimal numbers are expected
+fn number(v: &Value, name: &str) -> Result<f64, String> {
+    match v {
+        Value::Float(f) => Ok(*f),
+        Value::Integer(n) => Ok(*n as f64),
+        _ => Err(format!("{} must be a number", name)),
+    }
+}
+
+fn ratio(v: &Value, name: &str) -> Result<f64, String> {
+    match number(v, name)? {
+        r if r > 0.0 && r <= 1.0 => Ok(r),
+        _ => Err(format!("{} must be more than 0 and at most 1", name)),
+    }
+}
+
+fn colour(v: &Value, name: &str) -> Result<crossterm::style::Color, String> {
+    theme::parse_colour(string(v, name)?).map_err(|e| format!("{}: {}", name, e))
+}
+
+//list of directories, "~/" being the home directory
+fn dirs(v: &Value, name: &str) -> Result<Vec<PathBuf>, String> {
+    let error = || format!("{} must be a list of directories", name);
+    let mut res = vec![];
+    for d in v.as_array().ok_or_else(error)? {
+        let d = d.as_str().ok_or_else(error)?;
+        match (d.strip_prefix("~/"), home()) {
+            (Some(rest), Some(home)) => res.push(home.join(rest)),
+            _ => res.push(PathBuf::from(d)),
+        }
+    }
+    Ok(res)
+}
@@ -0,0 +1,336 @@
+use crate::camera::ViewRect;
+use crate::error::Error;
+use crate::history::{Command, History};
+use crate::menu::{get_input, print_lines, wrap};
+use crate::rle;
+use crate::rule::Lattice;
+use crate::tty::read_event;
+use crate::transform::{Cell, Transform};
+use crate::GameOfLife;
+
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
+use crossterm::style::{self, Stylize};
+use crossterm::terminal::{self, Clear, ClearType};
+use crossterm::{cursor, execute, queue};
+
+use std::fs;
+use std::io::Stdout;
+
+//oldest clipboard entries are dropped past this number
+const CLIPBOARD_SIZE: usize = 10;
+
+//rectangle copied from the grid, the alive cells being relative to its top left corner
+#[derive(Clone)]
+struct Clip {
+    cells: Vec<Cell>,
+    width: usize,
+    height: usize,
+}
+impl Clip {
+    fn transform(&mut self, t: Transform) {
+        let bounds = (0, 0, self.width as isize - 1, self.height as isize - 1);
+        self.cells = t.apply(&self.cells, bounds);
+        if let Transform::Rotate(1) | Transform::Rotate(3) | Transform::Transpose = t {
+            std::mem::swap(&mut self.width, &mut self.height);
+        }
+    }
+}
+
+//editing session paused between two generations, the clipboard is kept from one session to
+//the next
+pub struct Editor {
+    cursor: (isize, isize),
+    //other corner of the selection, the cursor being the first one
+    mark: Option<(isize, isize)>,
+    //newest entry first
+    clipboard: Vec<Clip>,
+    selected: usize,
+    //clip following the cursor until it is placed
+    pasting: Option<Clip>,
+    message: String,
+}
+impl Editor {
+    pub fn new() -> Editor {
+        Editor {
+            cursor: (0, 0),
+            mark: None,
+            clipboard: vec![],
+            selected: 0,
+            pasting: None,
+            message: String::new(),
+        }
+    }
+
+    //min x, min y, max x, max y of the selection
+    fn selection(&self) -> Option<(isize, isize, isize, isize)> {
+        let (mx, my) = self.mark?;
+        let (cx, cy) = self.cursor;
+        Some((mx.min(cx), my.min(cy), mx.max(cx), my.max(cy)))
+    }
+
+    fn copy(&mut self, game: &mut GameOfLife, history: &mut History, cut: bool) {
+        let (min_x, min_y, max_x, max_y) = match self.selection() {
+            Some(s) => s,
+            None => {
+                self.message = "press 's' at both corners to select".to_string();
+                return;
+            }
+        };
+        let cells: Vec<Cell> = game
+            .game_grid
+            .alive_cells_with_colour()
+            .into_iter()
+            .filter(|(x, y, _)| (min_x..=max_x).contains(x) && (min_y..=max_y).contains(y))
+            .collect();
+        if cut {
+            history.record(game, Command::Cut, |g| {
+                for (x, y, _) in cells.iter() {
+                    g.game_grid.set_cell(*x, *y, false);
+                }
+            });
+        }
+        self.message = format!("{} {} cells", if cut { "cut" } else { "copied" }, cells.len());
+        self.clipboard.insert(
+            0,
+            Clip {
+                cells: cells
+                    .iter()
+                    .map(|(x, y, c)| (x - min_x, y - min_y, *c))
+                    .collect(),
+                width: (max_x - min_x + 1) as usize,
+                height: (max_y - min_y + 1) as usize,
+            },
+        );
+        self.clipboard.truncate(CLIPBOARD_SIZE);
+        self.selected = 0;
+        self.mark = None;
+    }
+
+    //goes through the clipboard history, the clip being pasted changes with it
+    fn select_clip(&mut self, step: isize) {
+        if self.clipboard.is_empty() {
+            return;
+        }
+        let n = self.clipboard.len() as isize;
+        self.selected = (self.selected as isize + step).rem_euclid(n) as usize;
+        if self.pasting.is_some() {
+            self.pasting = Some(self.clipboard[self.selected].clone());
+        }
+    }
+
+    //alive cells of the clip are added at the cursor
+    fn paste(&mut self, game: &mut GameOfLife, history: &mut History) {
+        if let Some(clip) = self.pasting.take() {
+            let (x, y) = self.cursor;
+            let cells: Vec<Cell> = clip.cells.iter().map(|(i, j, c)| (x + i, y + j, *c)).collect();
+            history.record(game, Command::Paste, |g| g.game_grid.set_cells(&cells));
+            self.message = format!("pasted {} cells", cells.len());
+        }
+    }
+
+    #[allow(unused_must_use)]
+    fn export(&mut self, so: &mut Stdout, game: &GameOfLife) -> Result<(), Error> {
+        let clip = match self.clipboard.get(self.selected) {
+            Some(c) => c,
+            None => {
+                self.message = "the clipboard is empty".to_string();
+                return Ok(());
+            }
+        };
+        let path = get_input(so, "Enter the file to write the clipboard entry to (RLE)")?;
+        let text = rle::encode(&clip.cells, clip.width, clip.height, &game.rule);
+        self.message = match fs::write(&path, text) {
+            Ok(()) => format!("written to {}", path),
+            Err(e) => format!("could not write {}: {}", path, e),
+        };
+        Ok(())
+    }
+
+    //the generations can be undone at once
+    fn run_generations(
+        &mut self,
+        so: &mut Stdout,
+        game: &mut GameOfLife,
+        history: &mut History,
+    ) -> Result<(), Error> {
+        let n = get_input(so, "Enter the number of generations to run")?;
+        let n: usize = match n.trim().parse() {
+            Ok(n) => n,
+            Err(_) => {
+                self.message = "invalid number of generations".to_string();
+                return Ok(());
+            }
+        };
+        history.record(game, Command::Run(n), |g| {
+            for _ in 0..n {
+                g.next();
+            }
+        });
+        self.message = format!("ran {} generations", n);
+        Ok(())
+    }
+
+    fn move_cursor(&mut self, camera: &mut ViewRect, dx: isize, dy: isize) {
+        self.cursor.0 += dx;
+        self.cursor.1 += dy;
+        //the camera follows the cursor
+        let (x, y) = self.cursor;
+        if x < camera.x {
+            camera.move_left(camera.x - x);
+        } else if x >= camera.x + camera.x_len {
+            camera.move_right(x - camera.x - camera.x_len + 1);
+        }
+        if y < camera.y {
+            camera.move_up(camera.y - y);
+        } else if y >= camera.y + camera.y_len {
+            camera.move_down(y - camera.y - camera.y_len + 1);
+        }
+    }
+
+    #[allow(unused_must_use)]
+    fn show(
+        &mut self,
+        so: &mut Stdout,
+        game: &mut GameOfLife,
+        camera: &mut ViewRect,
+    ) -> Result<(), Error> {
+        queue!(so, Clear(ClearType::All), cursor::MoveTo(0, 0));
+        let help = match self.pasting {
+            Some(_) => "arrows to move; 'r'/'h'/'v'/'t' to rotate/flip/transpose; '['/']' for other entries; enter to place; esc to cancel",
+            None => "arrows to move; space to change a cell; 's' to select; 'c' to copy; 'x' to cut; 'p' to paste; 'w' to write to a file; 'n' to run generations; 'z' to undo; 'y' to redo; 'e' to go back",
+        };
+        let mut items: Vec<String> = help.split("; ").map(String::from).collect();
+        items.push("Editing".to_string());
+        items.push(format!(
+            "Clipboard: {}/{}",
+            (self.selected + 1).min(self.clipboard.len()),
+            self.clipboard.len()
+        ));
+        if !self.message.is_empty() {
+            items.push(self.message.clone());
+        }
+        let size = terminal::size().map_err(Error::Terminal)?;
+        let lines = print_lines(so, &wrap(&items, size.0));
+        camera.fit(size.0, size.1, lines);
+        game.show_in_camera(so, camera);
+
+        //cells are drawn again over the grid, below the lines of text and the border
+        let top = lines as isize + 1;
+        let mut overlay = |x: isize, y: isize, text: style::StyledContent<&str>| {
+            if (camera.x..camera.x + camera.x_len).contains(&x)
+                && (camera.y..camera.y + camera.y_len).contains(&y)
+            {
+                let (col, row) = (x - camera.x + 1, y - camera.y + top);
+                queue!(
+                    so,
+                    cursor::MoveTo(col as u16, row as u16),
+                    style::PrintStyledContent(text)
+                );
+            }
+        };
+        if let Some((min_x, min_y, max_x, max_y)) = self.selection() {
+            for y in min_y..=max_y {
+                for x in min_x..=max_x {
+                    let t = if game.game_grid.is_alive(x, y) { "█" } else { "+" };
+                    overlay(x, y, t.white());
+                }
+            }
+        }
+        if let Some(clip) = &self.pasting {
+            for (i, j, _) in clip.cells.iter() {
+                overlay(self.cursor.0 + i, self.cursor.1 + j, "▒".white());
+            }
+        }
+        let (col, row) = (self.cursor.0 - camera.x + 1, self.cursor.1 - camera.y + top);
+        execute!(so, cursor::MoveTo(col as u16, row as u16));
+        Ok(())
+    }
+
+    //returns when the user goes back to the simulation
+    pub fn run(
+        &mut self,
+        so: &mut Stdout,
+        game: &mut GameOfLife,
+        camera: &mut ViewRect,
+        history: &mut History,
+    ) -> Result<(), Error> {
+        //cells are one character wide only on square grids, and puzzles have their own budget of
+        //cells to change
+        if game.rule.lattice != Lattice::Square
+            || game.rule.elementary_number().is_some()
+            || game.puzzle.is_some()
+        {
+            return Ok(());
+        }
+        self.cursor = (camera.x + camera.x_len / 2, camera.y + camera.y_len / 2);
+        self.message.clear();
+
+        loop {
+            self.show(so, game, camera)?;
+            let key = match read_event()? {
+                Event::Key(KeyEvent { code, modifiers }) if modifiers == KeyModifiers::NONE => code,
+                _ => continue,
+            };
+            match key {
+                KeyCode::Up => self.move_cursor(camera, 0, -1),
+                KeyCode::Down => self.move_cursor(camera, 0, 1),
+                KeyCode::Left => self.move_cursor(camera, -1, 0),
+                KeyCode::Right => self.move_cursor(camera, 1, 0),
+                KeyCode::Char('[') => self.select_clip(-1),
+                KeyCode::Char(']') => self.select_clip(1),
+                _ => (),
+            }
+            if let Some(clip) = self.pasting.as_mut() {
+                match key {
+                    KeyCode::Char('r') => clip.transform(Transform::Rotate(1)),
+                    KeyCode::Char('h') => clip.transform(Transform::FlipHorizontal),
+                    KeyCode::Char('v') => clip.transform(Transform::FlipVertical),
+                    KeyCode::Char('t') => clip.transform(Transform::Transpose),
+                    KeyCode::Enter => self.paste(game, history),
+                    KeyCode::Esc => self.pasting = None,
+                    _ => (),
+                }
+                continue;
+            }
+            match key {
+                KeyCode::Char(' ') => {
+                    let (x, y) = self.cursor;
+                    let alive = game.game_grid.is_alive(x, y);
+                    history.record(game, Command::Edit, |g| g.game_grid.set_cell(x, y, !alive));
+                }
+                KeyCode::Char('s') => {
+                    self.mark = match self.mark {
+                        Some(_) => None,
+                        None => Some(self.cursor),
+                    }
+                }
+                KeyCode::Char('c') => self.copy(game, history, false),
+                KeyCode::Char('x') => self.copy(game, history, true),
+                KeyCode::Char('n') => self.run_generations(so, game, history)?,
+                KeyCode::Char('z') => {
+                    self.message = match history.undo(game) {
+                        Some(command) => format!("undid {}", command),
+                        None => "nothing to undo".to_string(),
+                    }
+                }
+                KeyCode::Char('y') => {
+                    self.message = match history.redo(game) {
+                        Some(command) => format!("redid {}", command),
+                        None => "nothing to redo".to_string(),
+                    }
+                }
+                KeyCode::Char('p') => match self.clipboard.get(self.selected) {
+                    Some(clip) => self.pasting = Some(clip.clone()),
+                    None => self.message = "the clipboard is empty".to_string(),
+                },
+                KeyCode::Char('w') => self.export(so, game)?,
+                KeyCode::Char('e') | KeyCode::Esc => break,
+                _ => (),
+            }
+        }
+
+        // cells added on the border of the grid must have room for their neighbours
+        game.game_grid.update_edges(game.rule.radius());
+        Ok(())
+    }
+}
@@ -0,0 +1,97 @@
+use crate::rule::Rule;
+use crate::{Cell, CellRow, GameGrid, UniqueCoordinates};
+
+//oldest generations are dropped past this number of rows, or of cells for the patterns that
+//keep growing
+const MAX_GENERATIONS: usize = 2_000;
+const MAX_CELLS: usize = 2_000_000;
+
+//one dimensional automata keep every generation as a row of the grid, the newest one being the
+//last, which draws the space-time diagram
+impl GameGrid {
+    //the first row of the pattern is the first generation, the empty rows above it are left out
+    pub fn init_elementary(&mut self) {
+        while self.grid.len() > 1 && !self.is_alive_row(0) {
+            self.remove_first_row();
+        }
+        self.grid.truncate(1);
+        self.alive_cells = UniqueCoordinates::new();
+        self.init_alive_cells();
+        self.widen_last_generation();
+    }
+
+    pub fn next_elementary(&mut self, rule: &Rule) -> bool {
+        self.widen_last_generation();
+
+        let previous = self.grid.last().unwrap();
+        let background = previous.background;
+        let all = if background { 0b111 } else { 0 };
+        let mut next = CellRow {
+            row: vec![],
+            y: previous.y + 1,
+            background: rule.next_state(false, all),
+        };
+        let mut changed = next.background != background;
+
+        for (i, c) in previous.row.iter().enumerate() {
+            let left = match i {
+                0 => background,
+                _ => previous.row[i - 1].is_alive,
+            };
+            let right = match previous.row.get(i + 1) {
+                Some(r) => r.is_alive,
+                None => background,
+            };
+            let neighbours = left as u32 | (c.is_alive as u32) << 1 | (right as u32) << 2;
+            let alive = rule.next_state(c.is_alive, neighbours);
+
+            changed |= alive != c.is_alive;
+            if alive {
+                self.alive_cells.push((c.x, next.y));
+            }
+            next.row.push(Cell::new(c.x, alive));
+        }
+        self.grid.push(next);
+
+        let mut cells: usize = self.grid.iter().map(|r| r.row.len()).sum();
+        while self.grid.len() > MAX_GENERATIONS || (cells > MAX_CELLS && self.grid.len() > 1) {
+            let oldest = self.grid.remove(0);
+            cells -= oldest.row.len();
+            for c in oldest.row.iter() {
+                self.alive_cells.remove((c.x, oldest.y));
+            }
+        }
+
+        changed
+    }
+
+    //make sure the cells around the last generation are in the background state so that the
+    //cells outside of the grid stay in the background state next generation. Only the last
+    //generation is widened, the older ones being shorter
+    fn widen_last_generation(&mut self) {
+        let last = self.grid.last_mut().unwrap();
+        let background = last.background;
+        if last.row.first().unwrap().is_alive != background {
+            last.prepend_cell(background);
+            if background {
+                self.alive_cells.push((last.row.first().unwrap().x, last.y));
+            }
+        }
+        if last.row.last().unwrap().is_alive != background {
+            last.append_cell(background);
+            if background {
+                self.alive_cells.push((last.row.last().unwrap().x, last.y));
+            }
+        }
+    }
+
+    //state of the cells missing from a generation shorter than the last one, None outside of
+    //the grid
+    pub fn background_at(&mut self, x: isize, y: isize) -> Option<bool> {
+        let last = self.grid.last().unwrap();
+        if x < last.row.first().unwrap().x || x > last.row.last().unwrap().x {
+            return None;
+        }
+        self.get_row(y).map(|r| r.background)
+    }
+}
@@ -0,0 +1,119 @@
+use std::fmt;
+use std::io;
+
+//errors shown to the user, above the menu or when the program stops
+#[derive(Debug)]
+pub enum Error {
+    //file that can't be read, the column is 0 when the whole line is wrong
+    Parse {
+        file: Option<String>,
+        line: usize,
+        column: usize,
+        reason: String,
+    },
+    //character of a pattern that is neither an alive nor a dead cell
+    UnknownCharacter {
+        file: Option<String>,
+        line: usize,
+        column: usize,
+        character: char,
+    },
+    MissingFile(String),
+    //the keys can't be read or the grid drawn
+    Terminal(io::Error),
+    //other errors reading or writing files
+    Io(io::Error),
+    //rule, argument or setting that can't be used
+    Invalid(String),
+}
+
+impl Error {
+    //line_index starts at 0, lines are shown starting at 1
+    pub fn parse(line_index: usize, column: usize, reason: &str) -> Error {
+        Error::Parse {
+            file: None,
+            line: line_index + 1,
+            column,
+            reason: reason.to_string(),
+        }
+    }
+
+    pub fn invalid(reason: &str) -> Error {
+        Error::Invalid(reason.to_string())
+    }
+
+    //errors of parsers that don't know the name of the file they read
+    pub fn in_file(self, path: &str) -> Error {
+        match self {
+            Error::Parse {
+                file: None,
+                line,
+                column,
+                reason,
+            } => Error::Parse {
+                file: Some(path.to_string()),
+                line,
+                column,
+                reason,
+            },
+            Error::UnknownCharacter {
+                file: None,
+                line,
+                column,
+                character,
+            } => Error::UnknownCharacter {
+                file: Some(path.to_string()),
+                line,
+                column,
+                character,
+            },
+            e => e,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let position = |f: &mut fmt::Formatter, file: &Option<String>, line, column| {
+            if let Some(file) = file {
+                write!(f, "{}: ", file)?;
+            }
+            match column {
+                0 => write!(f, "line {}: ", line),
+                _ => write!(f, "line {}, column {}: ", line, column),
+            }
+        };
+        match self {
+            Error::Parse {
+                file,
+                line,
+                column,
+                reason,
+            } => {
+                position(f, file, *line, *column)?;
+                write!(f, "{}", reason)
+            }
+            Error::UnknownCharacter {
+                file,
+                line,
+                column,
+                character,
+            } => {
+                position(f, file, *line, *column)?;
+                write!(f, "unknown character '{}'", character)
+            }
+            Error::MissingFile(path) => write!(f, "{}: no such file", path),
+            Error::Terminal(e) => write!(f, "terminal error: {}", e),
+            Error::Io(e) => write!(f, "{}", e),
+            Error::Invalid(reason) => write!(f, "{}", reason),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Error {
+        Error::Io(e)
+    }
+}
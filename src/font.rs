@@ -0,0 +1,453 @@
+use crate::error::Error;
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::str::FromStr;
+
+const PSF1_MAGIC: [u8; 2] = [0x36, 0x04];
+const PSF2_MAGIC: [u8; 4] = [0x72, 0xb5, 0x4a, 0x86];
+//largest size and offset of a BDF bounding box, so that their sums can't overflow
+const MAX_BDF_SIZE: isize = 1024;
+
+//glyphs of the built-in font in the text of .gol files, every glyph is 5 cells high and has
+//a dead column on each side. Letters are only drawn once, lowercase and uppercase look the same
+const GLYPHS: [(char, [&str; 5]); 69] = [
+    (' ', ["-----", "-----", "-----", "-----", "-----"]),
+    ('!', ["-a-", "-a-", "-a-", "---", "-a-"]),
+    ('"', ["-a-a-", "-a-a-", "-----", "-----", "-----"]),
+    ('#', ["--a-a--", "-aaaaa-", "--a-a--", "-aaaaa-", "--a-a--"]),
+    ('$', ["--aaaa-", "-a-a---", "--aaa--", "---a-a-", "-aaaa--"]),
+    ('%', ["-a---a-", "----a--", "---a---", "--a----", "-a---a-"]),
+    ('&', ["--aa---", "-a--a--", "--aa---", "-a--a--", "--aa-a-"]),
+    ('\'', ["-a-", "-a-", "---", "---", "---"]),
+    ('(', ["--a-", "-a--", "-a--", "-a--", "--a-"]),
+    (')', ["-a--", "--a-", "--a-", "--a-", "-a--"]),
+    ('*', ["-----", "-a-a-", "--a--", "-a-a-", "-----"]),
+    ('+', ["-----", "--a--", "-aaa-", "--a--", "-----"]),
+    (',', ["----", "----", "----", "--a-", "-a--"]),
+    ('-', ["-----", "-----", "-aaa-", "-----", "-----"]),
+    ('.', ["---", "---", "---", "---", "-a-"]),
+    ('/', ["-----a-", "----a--", "---a---", "--a----", "-a-----"]),
+    ('0', ["--a--", "-a-a-", "-a-a-", "-a-a-", "--a--"]),
+    ('1', ["--a--", "-aa--", "--a--", "--a--", "-aaa-"]),
+    ('2', ["-aaa-", "---a-", "-aaa-", "-a---", "-aaa-"]),
+    ('3', ["-aaa-", "---a-", "--aa-", "---a-", "-aaa-"]),
+    ('4', ["-a-a-", "-a-a-", "-aaa-", "---a-", "---a-"]),
+    ('5', ["-aaa-", "-a---", "-aaa-", "---a-", "-aaa-"]),
+    ('6', ["-aaa-", "-a---", "-aaa-", "-a-a-", "-aaa-"]),
+    ('7', ["-aaa-", "---a-", "---a-", "--a--", "--a--"]),
+    ('8', ["-aaa-", "-a-a-", "-aaa-", "-a-a-", "-aaa-"]),
+    ('9', ["-aaa-", "-a-a-", "-aaa-", "---a-", "-aaa-"]),
+    (':', ["---", "-a-", "---", "-a-", "---"]),
+    (';', ["----", "--a-", "----", "--a-", "-a--"]),
+    ('<', ["---a-", "--a--", "-a---", "--a--", "---a-"]),
+    ('=', ["-----", "-aaa-", "-----", "-aaa-", "-----"]),
+    ('>', ["-a---", "--a--", "---a-", "--a--", "-a---"]),
+    ('?', ["-aaa-", "---a-", "--a--", "-----", "--a--"]),
+    ('@', ["--aaa--", "-a---a-", "-a-aaa-", "-a-----", "--aaa--"]),
+    ('[', ["-aa-", "-a--", "-a--", "-a--", "-aa-"]),
+    ('\\', ["-a-----", "--a----", "---a---", "----a--", "-----a-"]),
+    (']', ["-aa-", "--a-", "--a-", "--a-", "-aa-"]),
+    ('^', ["--a--", "-a-a-", "-----", "-----", "-----"]),
+    ('_', ["-----", "-----", "-----", "-----", "-aaa-"]),
+    ('`', ["-a--", "--a-", "----", "----", "----"]),
+    ('a', ["-----a-----", "----a-a----", "---aaaaa---", "--a-----a--", "-a-------a-"]),
+    ('b', ["-aaa--", "-a--a-", "-aaa--", "-a--a-", "-aaaa-"]),
+    ('c', ["--aaaa-", "-a-----", "-a-----", "-a-----", "--aaaa-"]),
+    ('d', ["-aaa--", "-a--a-", "-a--a-", "-a--a-", "-aaa--"]),
+    ('e', ["-aaaa-", "-a----", "-aaaa-", "-a----", "-aaaa-"]),
+    ('f', ["-aaaa-", "-a----", "-aaaa-", "-a----", "-a----"]),
+    ('g', ["--aaaa-", "-a-----", "-a-aaa-", "-a--a--", "--aaa--"]),
+    ('h', ["-a---a-", "-a---a-", "-aaaaa-", "-a---a-", "-a---a-"]),
+    ('i', ["-aaa-", "--a--", "--a--", "--a--", "-aaa-"]),
+    ('j', ["-aaaaa-", "---a---", "---a---", "-a-a---", "-aaa---"]),
+    ('k', ["-a--a-", "-a-a--", "-aa---", "-a-a--", "-a--a-"]),
+    ('l', ["-a----", "-a----", "-a----", "-a----", "-aaaa-"]),
+    ('m', ["-a---a-", "-aa-aa-", "-a-a-a-", "-a---a-", "-a---a-"]),
+    ('n', ["-a---a-", "-aa--a-", "-a-a-a-", "-a--aa-", "-a---a-"]),
+    ('o', ["--aaa--", "-a---a-", "-a---a-", "-a---a-", "--aaa--"]),
+    ('p', ["-aaa--", "-a--a-", "-aaa--", "-a----", "-a----"]),
+    ('q', ["--aaa----", "-a---a---", "-a---a---", "-a--aa---", "--aaa-aa-"]),
+    ('r', ["-aaaa---", "-a---a--", "-aaaa---", "-a---a--", "-a----a-"]),
+    ('s', ["--aaaa-", "-a-----", "--aaa--", "-----a-", "-aaaa--"]),
+    ('t', ["-aaaaa-", "---a---", "---a---", "---a---", "---a---"]),
+    ('u', ["-a----a-", "-a----a-", "-a----a-", "-a----a-", "--aaaa--"]),
+    ('v', ["-a-------a-", "--a-----a--", "---a---a---", "----a-a----", "-----a-----"]),
+    ('w', ["-a---------a-", "-a----a----a-", "-a----a----a-", "--a--a-a--a--", "---aa---aa---"]),
+    ('x', ["-a---a-", "--a-a--", "---a---", "--a-a--", "-a---a-"]),
+    ('y', ["-a---a-", "--a-a--", "---a---", "---a---", "---a---"]),
+    ('z', ["-aaaaa-", "----a--", "---a---", "--a----", "-aaaaa-"]),
+    ('{', ["--aa-", "--a--", "-a---", "--a--", "--aa-"]),
+    ('|', ["-a-", "-a-", "-a-", "-a-", "-a-"]),
+    ('}', ["-aa--", "--a--", "---a-", "--a--", "-aa--"]),
+    ('~', ["-------", "--a----", "-a-a-a-", "----a--", "-------"]),
+];
+
+//rows of cells of a glyph, true for alive cells
+type Glyph = Vec<Vec<bool>>;
+
+#[derive(Clone, Copy)]
+pub enum Align {
+    Left,
+    Center,
+    Right,
+}
+impl FromStr for Align {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Align, ()> {
+        match s {
+            "left" => Ok(Align::Left),
+            "center" => Ok(Align::Center),
+            "right" => Ok(Align::Right),
+            _ => Err(()),
+        }
+    }
+}
+
+//how the lines of a text are put together
+pub struct Layout {
+    //dead columns between two characters
+    pub letter_spacing: usize,
+    //dead rows between two lines
+    pub line_spacing: usize,
+    //alignment of lines shorter than the longest one
+    pub align: Align,
+}
+impl Layout {
+    pub fn new() -> Layout {
+        Layout {
+            letter_spacing: 0,
+            line_spacing: 2,
+            align: Align::Left,
+        }
+    }
+}
+
+//font used to draw words, the built-in one or a BDF or PSF bitmap font
+pub struct Font {
+    glyphs: HashMap<char, Glyph>,
+    //every glyph is this number of cells high
+    height: usize,
+    //every cell of a glyph is drawn as a square of scale x scale cells
+    scale: usize,
+}
+impl Font {
+    pub fn builtin(scale: usize) -> Font {
+        let glyphs = GLYPHS
+            .iter()
+            .map(|(c, rows)| {
+                let glyph = rows.iter().map(|r| r.chars().map(|c| c == 'a').collect());
+                (*c, glyph.collect())
+            })
+            .collect();
+        Font {
+            glyphs,
+            height: 5,
+            scale,
+        }
+    }
+
+    //the format is found from the content of the file
+    pub fn load(path: &str, scale: usize) -> Result<Font, Error> {
+        let bytes = fs::read(path).map_err(|e| match e.kind() {
+            io::ErrorKind::NotFound => Error::MissingFile(path.to_string()),
+            _ => Error::Io(e),
+        })?;
+        let parsed = if bytes.starts_with(&PSF1_MAGIC) {
+            parse_psf1(&bytes)
+        } else if bytes.starts_with(&PSF2_MAGIC) {
+            parse_psf2(&bytes)
+        } else if bytes.starts_with(b"STARTFONT") {
+            parse_bdf(&String::from_utf8_lossy(&bytes))
+        } else {
+            Err("not a BDF or PSF font".to_string())
+        };
+        let (glyphs, height) =
+            parsed.map_err(|e| Error::Invalid(format!("invalid font '{}': {}", path, e)))?;
+        Ok(Font {
+            glyphs,
+            height,
+            scale,
+        })
+    }
+
+    //text in the format of .gol files drawing every line of s with the font
+    pub fn render(&self, s: &str, layout: &Layout) -> String {
+        let lines: Vec<Vec<String>> = s.lines().map(|l| self.render_line(l, layout)).collect();
+        let width = lines.iter().map(|l| l[0].len()).max().unwrap_or(0);
+        let empty = "-".repeat(width);
+
+        let mut rows = vec![];
+        for (i, line) in lines.iter().enumerate() {
+            if i > 0 {
+                for _ in 0..layout.line_spacing {
+                    rows.push(empty.clone());
+                }
+            }
+            let margin = width - line[0].len();
+            let left = match layout.align {
+                Align::Left => 0,
+                Align::Center => margin / 2,
+                Align::Right => margin,
+            };
+            for r in line.iter() {
+                rows.push(format!("{}{}{}", &empty[..left], r, &empty[..margin - left]));
+            }
+        }
+        rows.join("\n")
+    }
+
+    //rows of the glyphs of the characters of the line next to each other
+    fn render_line(&self, line: &str, layout: &Layout) -> Vec<String> {
+        let mut rows = vec![String::new(); self.height * self.scale];
+        for (i, c) in line.chars().enumerate() {
+            let spacing = if i > 0 { layout.letter_spacing } else { 0 };
+            for (r, g) in rows.iter_mut().zip(self.glyph(c)) {
+                r.push_str(&"-".repeat(spacing));
+                r.push_str(&g);
+            }
+        }
+        rows
+    }
+
+    //rows of the glyph of c, characters missing from the font are looked up in the other case
+    //and then drawn as a box
+    fn glyph(&self, c: char) -> Vec<String> {
+        let found = self
+            .glyphs
+            .get(&c)
+            .or_else(|| self.glyphs.get(&c.to_lowercase().next().unwrap()))
+            .or_else(|| self.glyphs.get(&c.to_uppercase().next().unwrap()));
+        let placeholder;
+        let glyph = match found {
+            Some(g) => g,
+            None => {
+                placeholder = self.placeholder();
+                &placeholder
+            }
+        };
+
+        let mut rows = vec![];
+        for r in glyph.iter() {
+            let row: String = r
+                .iter()
+                .map(|alive| if *alive { "a" } else { "-" }.repeat(self.scale))
+                .collect();
+            for _ in 0..self.scale {
+                rows.push(row.clone());
+            }
+        }
+        rows
+    }
+
+    //box as high as the font with a dead column on each side
+    fn placeholder(&self) -> Glyph {
+        let width = (self.height / 2 + 1).max(4);
+        (0..self.height)
+            .map(|y| {
+                let mut row = vec![false];
+                for x in 0..width {
+                    let side = x == 0 || x == width - 1;
+                    row.push(side || y == 0 || y == self.height - 1);
+                }
+                row.push(false);
+                row
+            })
+            .collect()
+    }
+}
+
+//rows of width bits starting with the most significant bit, each row using whole bytes
+fn bitmap(bytes: &[u8], width: usize, height: usize) -> Glyph {
+    let row_bytes = width.div_ceil(8);
+    (0..height)
+        .map(|y| {
+            (0..width)
+                .map(|x| bytes[y * row_bytes + x / 8] >> (7 - x % 8) & 1 == 1)
+                .collect()
+        })
+        .collect()
+}
+
+//BDF glyphs are placed in the bounding box of the font so that every glyph has the same height
+//and the same baseline
+fn parse_bdf(text: &str) -> Result<(HashMap<char, Glyph>, usize), String> {
+    let mut glyphs = HashMap::new();
+    let mut font_box = None;
+    let mut encoding = None;
+    let mut advance = None;
+    let mut glyph_box = None;
+
+    let mut lines = text.lines();
+    while let Some(line) = lines.next() {
+        let mut words = line.split_whitespace();
+        match words.next() {
+            Some("FONTBOUNDINGBOX") => font_box = Some(bounding_box(words)?),
+            Some("STARTCHAR") => {
+                encoding = None;
+                advance = None;
+                glyph_box = None;
+            }
+            //-1 for glyphs without a code point
+            Some("ENCODING") => {
+                encoding = words
+                    .next()
+                    .and_then(|n| n.parse().ok())
+                    .and_then(char::from_u32)
+            }
+            Some("DWIDTH") => {
+                advance = words
+                    .next()
+                    .and_then(|n| n.parse::<isize>().ok())
+                    .filter(|n| n.abs() <= MAX_BDF_SIZE)
+            }
+            Some("BBX") => glyph_box = Some(bounding_box(words)?),
+            Some("BITMAP") => {
+                let [font_width, font_height, font_x, font_y] =
+                    font_box.ok_or("BITMAP before FONTBOUNDINGBOX")?;
+                let [w, h, x, y] = glyph_box.ok_or("BITMAP without BBX")?;
+
+                let width = advance.unwrap_or(font_width).max(x - font_x + w).max(0);
+                let mut glyph = vec![vec![false; width as usize]; font_height.max(0) as usize];
+                //row of the top of the glyph in the font's box
+                let top = font_height + font_y - h - y;
+                for i in 0..h {
+                    let row = lines.next().ok_or("missing BITMAP rows")?.trim();
+                    let bytes = hex(row)?;
+                    for j in 0..w {
+                        let alive = match bytes.get(j as usize / 8) {
+                            Some(b) => b >> (7 - j % 8) & 1 == 1,
+                            None => false,
+                        };
+                        let (gx, gy) = (x - font_x + j, top + i);
+                        if alive && (0..width).contains(&gx) && (0..font_height).contains(&gy) {
+                            glyph[gy as usize][gx as usize] = true;
+                        }
+                    }
+                }
+                if let Some(c) = encoding {
+                    glyphs.insert(c, glyph);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    let height = font_box.ok_or("missing FONTBOUNDINGBOX")?[1];
+    if height <= 0 {
+        return Err("the glyphs have no rows".to_string());
+    }
+    Ok((glyphs, height as usize))
+}
+
+//width, height and offsets of the lower left corner
+fn bounding_box<'a>(words: impl Iterator<Item = &'a str>) -> Result<[isize; 4], String> {
+    let numbers: Vec<isize> = words.filter_map(|w| w.parse().ok()).collect();
+    match numbers.as_slice() {
+        [w, h, x, y] if numbers.iter().all(|n| n.abs() <= MAX_BDF_SIZE) => Ok([*w, *h, *x, *y]),
+        _ => Err("invalid bounding box".to_string()),
+    }
+}
+
+fn hex(row: &str) -> Result<Vec<u8>, String> {
+    (0..row.len())
+        .step_by(2)
+        .map(|i| {
+            row.get(i..i + 2)
+                .and_then(|b| u8::from_str_radix(b, 16).ok())
+                .ok_or(format!("invalid BITMAP row '{}'", row))
+        })
+        .collect()
+}
+
+//PSF1 glyphs are 8 cells wide, there are 256 of them or 512 with the first bit of the mode
+fn parse_psf1(bytes: &[u8]) -> Result<(HashMap<char, Glyph>, usize), String> {
+    let (mode, height) = match bytes.get(2..4) {
+        Some(header) => (header[0], header[1] as usize),
+        None => return Err("the file is too short".to_string()),
+    };
+    if height == 0 {
+        return Err("the glyphs have no rows".to_string());
+    }
+    let count: usize = if mode & 1 == 1 { 512 } else { 256 };
+    let end = count
+        .checked_mul(height)
+        .and_then(|size| size.checked_add(4))
+        .ok_or("the file is too short")?;
+    let bitmaps = bytes.get(4..end).ok_or("the file is too short")?;
+
+    //the unicode table gives the characters of each glyph as u16 ending with 0xFFFF, sequences
+    //of characters starting with 0xFFFE are ignored
+    let mut chars: Vec<Vec<char>> = (0..count as u32).map(|i| char::from_u32(i).into_iter().collect()).collect();
+    if mode & 2 == 2 {
+        let table: Vec<u16> = bytes[end..]
+            .chunks_exact(2)
+            .map(|b| u16::from_le_bytes([b[0], b[1]]))
+            .collect();
+        for (i, entry) in table.split(|n| *n == 0xFFFF).take(count).enumerate() {
+            let single = entry.split(|n| *n == 0xFFFE).next().unwrap();
+            chars[i] = single.iter().filter_map(|n| char::from_u32(*n as u32)).collect();
+        }
+    }
+
+    Ok((glyph_map(bitmaps, &chars, height, 8, height), height))
+}
+
+fn parse_psf2(bytes: &[u8]) -> Result<(HashMap<char, Glyph>, usize), String> {
+    let field = |i: usize| match bytes.get(i..i + 4) {
+        Some(b) => Ok(u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as usize),
+        None => Err("the file is too short".to_string()),
+    };
+    let header_size = field(8)?;
+    let flags = field(12)?;
+    let count = field(16)?;
+    let glyph_size = field(20)?;
+    let height = field(24)?;
+    let width = field(28)?;
+    if height == 0 {
+        return Err("the glyphs have no rows".to_string());
+    }
+    if width == 0 {
+        return Err("the glyphs have no columns".to_string());
+    }
+    match height.checked_mul(width.div_ceil(8)) {
+        Some(size) if size <= glyph_size => (),
+        _ => return Err("glyphs are smaller than their size".to_string()),
+    }
+    let end = count
+        .checked_mul(glyph_size)
+        .and_then(|size| size.checked_add(header_size))
+        .ok_or("the file is too short")?;
+    let bitmaps = bytes.get(header_size..end).ok_or("the file is too short")?;
+
+    //the unicode table gives the characters of each glyph in UTF-8 ending with 0xFF, sequences
+    //of characters starting with 0xFE are ignored
+    let mut chars: Vec<Vec<char>> = (0..count as u32).map(|i| char::from_u32(i).into_iter().collect()).collect();
+    if flags & 1 == 1 {
+        for (i, entry) in bytes[end..].split(|b| *b == 0xFF).take(count).enumerate() {
+            let single = entry.split(|b| *b == 0xFE).next().unwrap();
+            chars[i] = String::from_utf8_lossy(single).chars().collect();
+        }
+    }
+
+    Ok((glyph_map(bitmaps, &chars, glyph_size, width, height), height))
+}
+
+//glyph i of the bitmaps is used for the characters chars[i]
+fn glyph_map(
+    bitmaps: &[u8],
+    chars: &[Vec<char>],
+    glyph_size: usize,
+    width: usize,
+    height: usize,
+) -> HashMap<char, Glyph> {
+    let mut glyphs = HashMap::new();
+    for (i, g) in bitmaps.chunks_exact(glyph_size).enumerate() {
+        for c in chars[i].iter() {
+            glyphs.insert(*c, bitmap(g, width, height));
+        }
+    }
+    glyphs
+}
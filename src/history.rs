@@ -0,0 +1,290 @@
+use crate::rule::Lattice;
+use crate::{GameGrid, GameOfLife};
+
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::mem;
+
+//changes are dropped from the oldest one once they hold more cells than this
+const MAX_CELLS: usize = 200_000;
+const MAX_CHANGES: usize = 1000;
+//generations played one after the other start a new change past this number of cells, so that
+//the oldest ones can be dropped
+const MAX_PLAY_CELLS: usize = 20_000;
+
+#[derive(Clone, Copy)]
+pub enum Command {
+    Edit,
+    Cut,
+    Paste,
+    Transform,
+    Reverse,
+    //generations run at once
+    Run(usize),
+    //generations played one after the other, merged into one change until another command
+    Play(usize),
+}
+
+impl fmt::Display for Command {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Command::Edit => write!(f, "edit"),
+            Command::Cut => write!(f, "cut"),
+            Command::Paste => write!(f, "paste"),
+            Command::Transform => write!(f, "transformation"),
+            Command::Reverse => write!(f, "time reversal"),
+            Command::Run(n) | Command::Play(n) => write!(f, "{} generations", n),
+        }
+    }
+}
+
+//state of a cell before and after a command, None for a dead cell and the colour of an alive one
+type CellChange = (Option<u8>, Option<u8>);
+
+//what a command changed, enough to go back and forth without running it again (stochastic rules
+//would give another result)
+struct Change {
+    command: Command,
+    cells: HashMap<(isize, isize), CellChange>,
+    //cells of the previous generation in the second-order mode
+    previous: HashMap<(isize, isize), CellChange>,
+    generation: (isize, isize),
+    backwards: (bool, bool),
+}
+impl Change {
+    fn size(&self) -> usize {
+        self.cells.len() + self.previous.len()
+    }
+
+    //the next change happened right after this one
+    fn merge(&mut self, next: Change) {
+        merge_cells(&mut self.cells, next.cells);
+        merge_cells(&mut self.previous, next.previous);
+        self.generation.1 = next.generation.1;
+        self.backwards.1 = next.backwards.1;
+        if let (Command::Play(n), Command::Play(m)) = (self.command, next.command) {
+            self.command = Command::Play(n + m);
+        }
+    }
+}
+
+fn merge_cells(
+    cells: &mut HashMap<(isize, isize), CellChange>,
+    next: HashMap<(isize, isize), CellChange>,
+) {
+    for (coords, (before, after)) in next {
+        let change = cells.entry(coords).or_insert((before, after));
+        change.1 = after;
+        if change.0 == change.1 {
+            cells.remove(&coords);
+        }
+    }
+}
+
+fn diff(
+    before: &HashMap<(isize, isize), u8>,
+    after: &HashMap<(isize, isize), u8>,
+) -> HashMap<(isize, isize), CellChange> {
+    let mut res = HashMap::new();
+    for (coords, colour) in before.iter() {
+        if after.get(coords) != Some(colour) {
+            res.insert(*coords, (Some(*colour), after.get(coords).cloned()));
+        }
+    }
+    for (coords, colour) in after.iter() {
+        if !before.contains_key(coords) {
+            res.insert(*coords, (None, Some(*colour)));
+        }
+    }
+    res
+}
+
+//state before a command of the cells it changes, kept while it runs so that only these cells
+//are compared afterwards
+#[derive(Default)]
+pub struct Journal(Option<HashMap<(isize, isize), Option<u8>>>);
+impl Journal {
+    //only the state before the first change of a cell is kept
+    pub fn note(&mut self, coords: (isize, isize), before: Option<u8>) {
+        if let Some(cells) = self.0.as_mut() {
+            cells.entry(coords).or_insert(before);
+        }
+    }
+}
+//copies of a grid, like the one used to count the neighbours, don't record
+impl Clone for Journal {
+    fn clone(&self) -> Journal {
+        Journal(None)
+    }
+}
+
+//state of the game kept while a command runs in the second-order mode, where the previous
+//generation is replaced as a whole
+struct Snapshot {
+    cells: HashMap<(isize, isize), u8>,
+    previous: HashMap<(isize, isize), u8>,
+    generation: isize,
+    backwards: bool,
+}
+
+impl GameGrid {
+    fn colours(&mut self) -> HashMap<(isize, isize), u8> {
+        let coords: Vec<(isize, isize)> = self.alive_cells.coords.iter().cloned().collect();
+        coords
+            .into_iter()
+            .map(|(x, y)| ((x, y), self.get_cell(x, y).unwrap().colour))
+            .collect()
+    }
+
+    fn state(&mut self, x: isize, y: isize) -> Option<u8> {
+        match self.is_alive(x, y) {
+            true => Some(self.get_cell(x, y).unwrap().colour),
+            false => None,
+        }
+    }
+
+    fn set_states(
+        &mut self,
+        cells: &HashMap<(isize, isize), CellChange>,
+        forward: bool,
+        radius: isize,
+    ) {
+        for ((x, y), (before, after)) in cells.iter() {
+            match if forward { after } else { before } {
+                Some(colour) => self.set_cells(&[(*x, *y, *colour)]),
+                None => self.set_cell(*x, *y, false),
+            }
+        }
+        self.update_edges(radius);
+    }
+}
+
+impl GameOfLife {
+    fn snapshot(&mut self) -> Snapshot {
+        Snapshot {
+            cells: self.game_grid.colours(),
+            previous: self.previous.as_mut().map_or_else(HashMap::new, |p| p.colours()),
+            generation: self.generation,
+            backwards: self.backwards,
+        }
+    }
+
+    fn apply(&mut self, change: &Change, forward: bool) {
+        let radius = self.rule.radius();
+        self.game_grid.set_states(&change.cells, forward, radius);
+        if let Some(previous) = self.previous.as_mut() {
+            previous.set_states(&change.previous, forward, radius);
+        }
+        let (generation, backwards) = if forward {
+            (change.generation.1, change.backwards.1)
+        } else {
+            (change.generation.0, change.backwards.0)
+        };
+        self.generation = generation;
+        self.backwards = backwards;
+    }
+}
+
+//commands done on the game that can be undone then redone
+pub struct History {
+    done: VecDeque<Change>,
+    undone: Vec<Change>,
+    //cells kept by all the changes
+    size: usize,
+}
+impl History {
+    pub fn new() -> History {
+        History {
+            done: VecDeque::new(),
+            undone: vec![],
+            size: 0,
+        }
+    }
+
+    //runs the command f and keeps what it changed
+    pub fn record<T>(
+        &mut self,
+        game: &mut GameOfLife,
+        command: Command,
+        f: impl FnOnce(&mut GameOfLife) -> T,
+    ) -> T {
+        //only the editor, on square grids, can undo
+        if game.rule.lattice != Lattice::Square || game.rule.elementary_number().is_some() {
+            return f(game);
+        }
+        let (res, change) = match game.is_second_order() {
+            true => {
+                let before = game.snapshot();
+                let res = f(game);
+                let after = game.snapshot();
+                let change = Change {
+                    command,
+                    cells: diff(&before.cells, &after.cells),
+                    previous: diff(&before.previous, &after.previous),
+                    generation: (before.generation, after.generation),
+                    backwards: (before.backwards, after.backwards),
+                };
+                (res, change)
+            }
+            false => {
+                let (generation, backwards) = (game.generation, game.backwards);
+                game.game_grid.journal = Journal(Some(HashMap::new()));
+                let res = f(game);
+                let touched = mem::take(&mut game.game_grid.journal).0.unwrap_or_default();
+                let cells = touched
+                    .into_iter()
+                    .map(|((x, y), before)| ((x, y), (before, game.game_grid.state(x, y))))
+                    .filter(|(_, (before, after))| before != after)
+                    .collect();
+                let change = Change {
+                    command,
+                    cells,
+                    previous: HashMap::new(),
+                    generation: (generation, game.generation),
+                    backwards: (backwards, game.backwards),
+                };
+                (res, change)
+            }
+        };
+        self.undone.clear();
+        match (self.done.back_mut(), command) {
+            (Some(last), Command::Play(_))
+                if matches!(last.command, Command::Play(_)) && last.size() < MAX_PLAY_CELLS =>
+            {
+                self.size -= last.size();
+                last.merge(change);
+                self.size += last.size();
+            }
+            _ => {
+                self.size += change.size();
+                self.done.push_back(change);
+            }
+        }
+
+        //the last change is dropped too when it is larger than the limit
+        while !self.done.is_empty() && (self.size > MAX_CELLS || self.done.len() > MAX_CHANGES) {
+            let oldest = self.done.pop_front().unwrap();
+            self.size -= oldest.size();
+        }
+        res
+    }
+
+    //returns the command that was undone
+    pub fn undo(&mut self, game: &mut GameOfLife) -> Option<Command> {
+        let change = self.done.pop_back()?;
+        game.apply(&change, false);
+        self.size -= change.size();
+        let command = change.command;
+        self.undone.push(change);
+        Some(command)
+    }
+
+    pub fn redo(&mut self, game: &mut GameOfLife) -> Option<Command> {
+        let change = self.undone.pop()?;
+        game.apply(&change, true);
+        self.size += change.size();
+        let command = change.command;
+        self.done.push_back(change);
+        Some(command)
+    }
+}
@@ -0,0 +1,142 @@
+use crossterm::event::{KeyCode, KeyModifiers};
+
+//what the keys do while the pattern is playing
+#[derive(Clone, Copy, PartialEq)]
+pub enum Action {
+    Quit,
+    SpeedUp,
+    SlowDown,
+    Up,
+    Down,
+    Left,
+    Right,
+    Zoom,
+    Unzoom,
+    Reverse,
+    Edit,
+    Rotate,
+    FlipHorizontal,
+    FlipVertical,
+    Transpose,
+    Scale,
+    Pause,
+}
+
+//name in the [keys] section of the configuration file and default key
+pub const ACTIONS: [(&str, Action, &str); 17] = [
+    ("quit", Action::Quit, "q"),
+    ("speed_up", Action::SpeedUp, "x"),
+    ("slow_down", Action::SlowDown, "c"),
+    ("up", Action::Up, "up"),
+    ("down", Action::Down, "down"),
+    ("left", Action::Left, "left"),
+    ("right", Action::Right, "right"),
+    ("zoom", Action::Zoom, "z"),
+    ("unzoom", Action::Unzoom, "u"),
+    ("reverse", Action::Reverse, "b"),
+    ("edit", Action::Edit, "e"),
+    ("rotate", Action::Rotate, "r"),
+    ("flip_horizontal", Action::FlipHorizontal, "h"),
+    ("flip_vertical", Action::FlipVertical, "v"),
+    ("transpose", Action::Transpose, "t"),
+    ("scale", Action::Scale, "m"),
+    ("pause", Action::Pause, "p"),
+];
+
+//keys are single characters or the names below
+const NAMED_KEYS: [(&str, KeyCode); 12] = [
+    ("up", KeyCode::Up),
+    ("down", KeyCode::Down),
+    ("left", KeyCode::Left),
+    ("right", KeyCode::Right),
+    ("space", KeyCode::Char(' ')),
+    ("enter", KeyCode::Enter),
+    ("esc", KeyCode::Esc),
+    ("tab", KeyCode::Tab),
+    ("backspace", KeyCode::Backspace),
+    ("delete", KeyCode::Delete),
+    ("pageup", KeyCode::PageUp),
+    ("pagedown", KeyCode::PageDown),
+];
+
+pub fn parse_key(s: &str) -> Result<KeyCode, String> {
+    let mut chars = s.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Ok(KeyCode::Char(c));
+    }
+    NAMED_KEYS
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(s))
+        .map(|(_, code)| *code)
+        .ok_or_else(|| format!("unknown key '{}'", s))
+}
+
+pub fn key_name(code: KeyCode) -> String {
+    match NAMED_KEYS.iter().find(|(_, c)| *c == code) {
+        Some((name, _)) => name.to_string(),
+        None => match code {
+            KeyCode::Char(c) => c.to_string(),
+            _ => "?".to_string(),
+        },
+    }
+}
+
+#[derive(Clone)]
+pub struct Keys {
+    bindings: Vec<(KeyCode, Action)>,
+}
+impl Default for Keys {
+    fn default() -> Keys {
+        Keys {
+            bindings: ACTIONS
+                .iter()
+                .map(|(_, action, key)| (parse_key(key).unwrap(), *action))
+                .collect(),
+        }
+    }
+}
+impl Keys {
+    //every action keeps its default key unless it is given another one
+    pub fn set(&mut self, action: Action, code: KeyCode) {
+        for b in self.bindings.iter_mut().filter(|b| b.1 == action) {
+            b.0 = code;
+        }
+    }
+
+    //the name of the first action sharing a key with another one
+    pub fn duplicate(&self) -> Option<(Action, Action)> {
+        for (i, (code, action)) in self.bindings.iter().enumerate() {
+            if let Some((_, other)) = self.bindings[i + 1..].iter().find(|b| b.0 == *code) {
+                return Some((*action, *other));
+            }
+        }
+        None
+    }
+
+    //shift with a letter gives the upper case letter, which is also the lower case letter's key
+    //so that shift and the movement keys shift the pattern
+    pub fn action(&self, code: KeyCode, modifiers: KeyModifiers) -> Option<Action> {
+        if !(modifiers == KeyModifiers::NONE || modifiers == KeyModifiers::SHIFT) {
+            return None;
+        }
+        let find = |code| self.bindings.iter().find(|b| b.0 == code).map(|b| b.1);
+        match code {
+            KeyCode::Char(c) if modifiers == KeyModifiers::SHIFT => {
+                find(code).or_else(|| find(KeyCode::Char(c.to_ascii_lowercase())))
+            }
+            _ => find(code),
+        }
+    }
+
+    //shown in the hud
+    pub fn name(&self, action: Action) -> String {
+        match self.bindings.iter().find(|b| b.1 == action) {
+            Some((code, _)) => key_name(*code),
+            None => "?".to_string(),
+        }
+    }
+}
+
+pub fn action_name(action: Action) -> &'static str {
+    ACTIONS.iter().find(|a| a.1 == action).unwrap().0
+}
@@ -0,0 +1,224 @@
+use crate::config::{self, Config};
+use crate::error::Error;
+
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+//directory under which the files bundled in the binary are found, when no other directory exists
+pub const EMBEDDED_ROOT: &str = "<embedded>";
+
+const EMBEDDED: &[(&str, &str)] = &[
+    ("patterns/acorn.gol", include_str!("../selection_files/acorn.gol")),
+    ("patterns/acorn.meta", include_str!("../selection_files/acorn.meta")),
+    ("patterns/block.gol", include_str!("../selection_files/block.gol")),
+    ("patterns/block.meta", include_str!("../selection_files/block.meta")),
+    ("patterns/f-pentomino.gol", include_str!("../selection_files/f-pentomino.gol")),
+    ("patterns/f-pentomino.meta", include_str!("../selection_files/f-pentomino.meta")),
+    ("patterns/glider-gun.gol", include_str!("../selection_files/glider-gun.gol")),
+    ("patterns/glider-gun.meta", include_str!("../selection_files/glider-gun.meta")),
+    (
+        "patterns/glider-hits-block.scene",
+        include_str!("../selection_files/glider-hits-block.scene"),
+    ),
+    (
+        "patterns/glider-hits-block.meta",
+        include_str!("../selection_files/glider-hits-block.meta"),
+    ),
+    ("patterns/glider.gol", include_str!("../selection_files/glider.gol")),
+    ("patterns/glider.meta", include_str!("../selection_files/glider.meta")),
+    ("patterns/t.gol", include_str!("../selection_files/t.gol")),
+    ("patterns/t.meta", include_str!("../selection_files/t.meta")),
+    (
+        "levels/break-the-block.level",
+        include_str!("../levels/break-the-block.level"),
+    ),
+    (
+        "levels/complete-the-glider.level",
+        include_str!("../levels/complete-the-glider.level"),
+    ),
+];
+
+#[derive(Clone, Copy)]
+pub enum Kind {
+    Patterns,
+    Levels,
+    Fonts,
+}
+impl Kind {
+    fn name(&self) -> &'static str {
+        match self {
+            Kind::Patterns => "patterns",
+            Kind::Levels => "levels",
+            Kind::Fonts => "fonts",
+        }
+    }
+
+    //list of directories separated like $PATH
+    fn variable(&self) -> &'static str {
+        match self {
+            Kind::Patterns => "GAME_OF_LIFE_PATTERNS",
+            Kind::Levels => "GAME_OF_LIFE_LEVELS",
+            Kind::Fonts => "GAME_OF_LIFE_FONTS",
+        }
+    }
+
+    //directory of the repository, for runs from its root
+    fn local_dir(&self) -> Option<&'static str> {
+        match self {
+            Kind::Patterns => Some("./selection_files/"),
+            Kind::Levels => Some("./levels/"),
+            Kind::Fonts => None,
+        }
+    }
+}
+
+//directories searched in order: the environment variable, the configuration file, the
+//repository's directory, then game-of-life/<kind> in $XDG_DATA_HOME (~/.local/share by default)
+//and $XDG_DATA_DIRS (/usr/local/share:/usr/share by default)
+fn search_path(kind: Kind, config: &Config) -> Vec<PathBuf> {
+    let mut dirs = vec![];
+    if let Some(v) = env::var_os(kind.variable()) {
+        dirs.extend(env::split_paths(&v));
+    }
+    dirs.extend(
+        match kind {
+            Kind::Patterns => &config.pattern_dirs,
+            Kind::Levels => &config.level_dirs,
+            Kind::Fonts => &config.font_dirs,
+        }
+        .iter()
+        .cloned(),
+    );
+    dirs.extend(kind.local_dir().map(PathBuf::from));
+
+    //the XDG directories hold the files of every program
+    let mut xdg = vec![];
+    match env::var_os("XDG_DATA_HOME") {
+        Some(d) if !d.is_empty() => xdg.push(PathBuf::from(d)),
+        _ => xdg.extend(config::home().map(|h| h.join(".local").join("share"))),
+    }
+    match env::var_os("XDG_DATA_DIRS") {
+        Some(d) if !d.is_empty() => xdg.extend(env::split_paths(&d)),
+        _ => xdg.extend(vec![
+            PathBuf::from("/usr/local/share"),
+            PathBuf::from("/usr/share"),
+        ]),
+    }
+    dirs.extend(xdg.iter().map(|d| d.join("game-of-life").join(kind.name())));
+    dirs
+}
+
+//directories where the files are found, resolved once at the start
+pub struct Library {
+    pub patterns: String,
+    pub levels: String,
+    fonts: Vec<PathBuf>,
+}
+impl Library {
+    pub fn new(config: &Config) -> Library {
+        Library {
+            patterns: first_dir(Kind::Patterns, config),
+            levels: first_dir(Kind::Levels, config),
+            fonts: search_path(Kind::Fonts, config)
+                .into_iter()
+                .filter(|d| d.is_dir())
+                .collect(),
+        }
+    }
+
+    //fonts given by name are looked for in the font directories, the built-in font being the
+    //fallback when no font is given
+    pub fn font_path(&self, name: &str) -> PathBuf {
+        if Path::new(name).is_file() {
+            return PathBuf::from(name);
+        }
+        self.fonts
+            .iter()
+            .map(|d| d.join(name))
+            .find(|p| p.is_file())
+            .unwrap_or_else(|| PathBuf::from(name))
+    }
+}
+
+fn first_dir(kind: Kind, config: &Config) -> String {
+    match search_path(kind, config).into_iter().find(|d| d.is_dir()) {
+        Some(d) => d.to_string_lossy().to_string(),
+        None => format!("{}/{}", EMBEDDED_ROOT, kind.name()),
+    }
+}
+
+//name of a file bundled in the binary, like "patterns/glider.gol"
+fn embedded_name(path: &Path) -> Option<String> {
+    let rest = path.strip_prefix(EMBEDDED_ROOT).ok()?;
+    let parts: Vec<String> = rest
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().to_string())
+        .collect();
+    Some(parts.join("/"))
+}
+
+//the functions below work like the ones of std::fs, with the embedded files too
+pub fn read_to_string<P: AsRef<Path>>(path: P) -> Result<String, Error> {
+    let path = path.as_ref();
+    let missing = || Error::MissingFile(path.display().to_string());
+    match embedded_name(path) {
+        Some(name) => EMBEDDED
+            .iter()
+            .find(|(n, _)| *n == name)
+            .map(|(_, contents)| contents.to_string())
+            .ok_or_else(missing),
+        None => fs::read_to_string(path).map_err(|e| file_error(path, e)),
+    }
+}
+
+//the errors of std::fs with the path they are about
+fn file_error(path: &Path, e: io::Error) -> Error {
+    match e.kind() {
+        io::ErrorKind::NotFound => Error::MissingFile(path.display().to_string()),
+        _ => Error::Io(io::Error::new(e.kind(), format!("{}: {}", path.display(), e))),
+    }
+}
+
+pub fn is_file(path: &Path) -> bool {
+    match embedded_name(path) {
+        Some(name) => EMBEDDED.iter().any(|(n, _)| *n == name),
+        None => path.is_file(),
+    }
+}
+
+pub fn is_dir(path: &Path) -> bool {
+    match embedded_name(path) {
+        Some(name) => EMBEDDED
+            .iter()
+            .any(|(n, _)| name.is_empty() || n.starts_with(&format!("{}/", name))),
+        None => path.is_dir(),
+    }
+}
+
+pub fn read_dir(dir: &Path) -> Result<Vec<PathBuf>, Error> {
+    let name = match embedded_name(dir) {
+        Some(n) => n,
+        None => {
+            return fs::read_dir(dir)
+                .and_then(|d| d.map(|e| e.map(|e| e.path())).collect())
+                .map_err(|e| file_error(dir, e))
+        }
+    };
+    let mut res: Vec<PathBuf> = vec![];
+    for (n, _) in EMBEDDED.iter() {
+        let rest = match name.as_str() {
+            "" => Some(*n),
+            _ => n.strip_prefix(&format!("{}/", name)),
+        };
+        //subdirectories are listed once
+        if let Some(child) = rest.and_then(|r| r.split('/').next()) {
+            let path = dir.join(child);
+            if !res.contains(&path) {
+                res.push(path);
+            }
+        }
+    }
+    Ok(res)
+}
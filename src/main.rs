@@ -1,158 +1,282 @@
 mod menu;
+mod browser;
 mod camera;
+mod chance;
+mod cli;
+mod config;
+mod editor;
+mod elementary;
+mod error;
+mod font;
+mod history;
+mod keys;
+mod library;
+mod margolus;
+mod metadata;
+mod puzzle;
+mod reversible;
+mod rle;
+mod rule;
+mod scene;
+mod theme;
+mod topology;
+mod transform;
+mod tty;
+mod versus;
 
 use menu::{game_selection, hud};
 use camera::ViewRect;
-
-use std::fs;
+use chance::Chance;
+use cli::Options;
+use editor::Editor;
+use error::Error;
+use font::{Font, Layout};
+use history::{Command, History, Journal};
+use keys::Action;
+use metadata::Metadata;
+use puzzle::Puzzle;
+use rule::{Lattice, Rule};
+use theme::Theme;
+use topology::Topology;
+use transform::Transform;
+use tty::{read_event, TerminalGuard};
+
+use std::collections::HashSet;
 use std::thread;
-use std::io::{self, stdout, Stdout};
+use std::io::{stdout, Stdout};
+use std::time::{Duration, Instant};
 
-use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyModifiers};
+use crossterm::event::{self, Event, KeyEvent, KeyModifiers};
 use crossterm::style::Print;
 use crossterm::style::{self, Stylize};
-use crossterm::terminal::{self, disable_raw_mode, enable_raw_mode, Clear, ClearType};
+use crossterm::terminal::{self, Clear, ClearType};
 use crossterm::{cursor, execute, queue};
 
-#[allow(unused_must_use)]
+//milliseconds between two checks of the keys while the pattern is paused
+const PAUSED_WAIT: f32 = 50.0;
+
 fn main() {
-    let mut speed: f32 = 4.0; // generations per seconds
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    //writes the configuration file with the default values, to be changed by hand
+    if args.first().map(String::as_str) == Some("--write-config") {
+        match config::write_default() {
+            Ok(path) => println!("{} written", path.display()),
+            Err(e) => eprintln!("{}", e),
+        }
+        return;
+    }
+    let options = match cli::parse(&args) {
+        Ok(o) => o,
+        Err(e) => {
+            eprintln!("{}", e);
+            return;
+        }
+    };
+
+    let res = {
+        //start using crossterm, which reads the keys from /dev/tty when the pattern is piped in
+        let mut stdout = stdout();
+        let _terminal = match TerminalGuard::new() {
+            Ok(t) => t,
+            Err(e) => {
+                eprintln!("{}", e);
+                return;
+            }
+        };
 
-    let mut count = 0;
+        // first the user select the file
+        match game_selection(&mut stdout, &options) {
+            Ok(Some(game)) => play(&mut stdout, game, &options).map(Some),
+            Ok(None) => Ok(None),
+            Err(e) => Err(e),
+        }
+        //end using crossterm, the alternate screen is left
+    };
+
+    match res {
+        Ok(Some(end)) => println!("{}", end),
+        Ok(None) => (),
+        Err(e) => eprintln!("{}", e),
+    }
+}
+
+//runs the pattern until it dies, the puzzle is over or the user quits, and tells how it ended
+#[allow(unused_must_use)]
+fn play(so: &mut Stdout, mut game: GameOfLife, options: &Options) -> Result<String, Error> {
     let mut quit = false;
+    let mut speed = options.speed.unwrap_or(options.config.speed); // generations per seconds
+
+    //levels are always deterministic
+    if game.puzzle.is_none() {
+        game.chance = options.chance.clone();
+    }
+    let mut last_time = Instant::now();
+    queue!(so, Clear(ClearType::All), cursor::MoveTo(0, 0));
+    game.theme = options.config.theme.clone();
+    let size = terminal::size().map_err(Error::Terminal)?;
+    let lines = hud(so, &game, speed, options.paused, &options.config.keys, size.0);
+    let mut camera = ViewRect::with_ratios(
+        0,
+        0,
+        size.0 as isize - 2,
+        size.1 as isize - lines as isize - 2,
+        options.config.view_width,
+        options.config.view_height,
+    );
+    let step = options.config.step;
+    if game.rule.elementary_number().is_some() {
+        let first = &game.game_grid.grid[0];
+        camera.x = (first.row[0].x + first.row.last().unwrap().x - camera.x_len) / 2;
+    }
+    game.show_in_camera(so, &camera);
+    let mut editor = Editor::new();
+    //generations are kept in the history so that they can be undone from the editor
+    let mut history = History::new();
+    let mut paused = options.paused;
+    loop {
+        //while paused the keys still work but no generation is run
+        if !paused {
+            if !history.record(&mut game, Command::Play(1), GameOfLife::next) {
+                break;
+            }
+            game.follow_generations(&mut camera);
+        }
 
-    //start using crossterm
-    let mut stdout = stdout();
-    enable_raw_mode().unwrap();
-
-    // first the user select the file
-    match game_selection(&mut stdout) {
-        Ok(mut game) => {
-            let mut last_time = std::time::SystemTime::now();
-            queue!(stdout, Clear(ClearType::All), cursor::MoveTo(0, 0));
-            hud(&mut stdout, count, speed);
-            let size = terminal::size().unwrap();
-            let mut camera = ViewRect::new(
-                0,
-                0,
-                size.0 as isize - 2,
-                size.1 as isize - 4,
-            );
-            let default_move = 2;
-            game.show_in_camera(&mut stdout, &camera);
-            while game.next() {
-                count += 1;
-
-                //making sure the generation rate is constant (if the speed is too high it waits for the
-                //code to finish executing
-                let elapsed = last_time.elapsed().unwrap().as_millis();
-                speed = (speed * 100.0).round() / 100.0;
-                let wait_time = 1000.0 / speed;
-                let real_wait_time = wait_time - elapsed as f32;
-
-                thread::sleep(std::time::Duration::from_millis(real_wait_time as u64));
-                last_time = std::time::SystemTime::now();
-
-                //using result from keys pressed
-                while event::poll(std::time::Duration::from_millis(1)).unwrap() {
-                    match event::read().unwrap() {
-                        Event::Key(KeyEvent {
-                            code: KeyCode::Char('x'),
-                            modifiers: KeyModifiers::NONE,
-                            //clearing the screen and printing our message
-                        }) => {
-                            if speed >= 1.0 {
-                                speed += 1.0;
-                            } else {
-                                speed *= 2.0;
-                            }
-                        }
-                        Event::Key(KeyEvent {
-                            code: KeyCode::Char('c'),
-                            modifiers: KeyModifiers::NONE,
-                        }) => {
-                            if speed >= 2.0 {
-                                speed -= 1.0;
-                            } else if speed >= 1.0 {
-                                speed *= 0.5;
-                            }
-                        }
-                        Event::Key(KeyEvent {
-                            code: KeyCode::Char('q'),
-                            modifiers: KeyModifiers::NONE,
-                        }) => quit = true,
-                        Event::Key(KeyEvent {
-                            code: KeyCode::Up,
-                            modifiers: KeyModifiers::NONE,
-                        }) => camera.move_up(default_move),
-                        Event::Key(KeyEvent {
-                            code: KeyCode::Down,
-                            modifiers: KeyModifiers::NONE,
-                        }) => camera.move_down(default_move),
-                        Event::Key(KeyEvent {
-                            code: KeyCode::Left,
-                            modifiers: KeyModifiers::NONE,
-                        }) => camera.move_left(default_move),
-                        Event::Key(KeyEvent {
-                            code: KeyCode::Right,
-                            modifiers: KeyModifiers::NONE,
-                        }) => camera.move_right(default_move),
-                        Event::Key(KeyEvent {
-                            code: KeyCode::Char('u'),
-                            modifiers: KeyModifiers::NONE,
-                        }) => camera.unzoom(1),
-                        Event::Key(KeyEvent {
-                            code: KeyCode::Char('z'),
-                            modifiers: KeyModifiers::NONE,
-                        }) => camera.zoom(1),
-                        Event::Resize(x, y) => {
-                            camera.x_max = x as isize - 2;
-                            camera.y_max = y as isize - 4;
-                        },
-                        _ => (),
-                        }
+        //making sure the generation rate is constant (if the speed is too high it waits for the
+        //code to finish executing
+        let elapsed = last_time.elapsed().as_millis();
+        speed = (speed * 100.0).round() / 100.0;
+        let wait_time = if paused { PAUSED_WAIT } else { 1000.0 / speed };
+        let real_wait_time = wait_time - elapsed as f32;
+
+        thread::sleep(Duration::from_millis(real_wait_time as u64));
+        last_time = Instant::now();
+
+        //using result from keys pressed
+        while event::poll(Duration::from_millis(1)).map_err(Error::Terminal)? {
+            let (code, modifiers) = match read_event()? {
+                Event::Key(KeyEvent { code, modifiers }) => (code, modifiers),
+                _ => continue,
+            };
+            //shift and a movement key shifts the pattern instead of the camera
+            let shift = modifiers == KeyModifiers::SHIFT;
+            let transform = match options.config.keys.action(code, modifiers) {
+                Some(Action::SpeedUp) => {
+                    if speed >= 1.0 {
+                        speed += 1.0;
+                    } else {
+                        speed *= 2.0;
                     }
-
-                if quit {
-                    break;
+                    None
                 }
-
-                //Display the new generation
-                queue!(stdout, Clear(ClearType::All), cursor::MoveTo(0, 0));
-                hud(&mut stdout, count, speed);
-                game.show_in_camera(&mut stdout, &camera);
+                Some(Action::SlowDown) => {
+                    if speed >= 2.0 {
+                        speed -= 1.0;
+                    } else if speed >= 1.0 {
+                        speed *= 0.5;
+                    }
+                    None
+                }
+                Some(Action::Quit) => {
+                    quit = true;
+                    None
+                }
+                Some(Action::Pause) => {
+                    paused = !paused;
+                    None
+                }
+                Some(Action::Up) if shift => Some(Transform::Translate(0, -1)),
+                Some(Action::Down) if shift => Some(Transform::Translate(0, 1)),
+                Some(Action::Left) if shift => Some(Transform::Translate(-1, 0)),
+                Some(Action::Right) if shift => Some(Transform::Translate(1, 0)),
+                Some(Action::Up) => {
+                    camera.move_up(step);
+                    None
+                }
+                Some(Action::Down) => {
+                    camera.move_down(step);
+                    None
+                }
+                Some(Action::Left) => {
+                    camera.move_left(step);
+                    None
+                }
+                Some(Action::Right) => {
+                    camera.move_right(step);
+                    None
+                }
+                Some(Action::Unzoom) => {
+                    camera.unzoom(1);
+                    None
+                }
+                Some(Action::Zoom) => {
+                    camera.zoom(1);
+                    None
+                }
+                Some(Action::Reverse) => {
+                    history.record(&mut game, Command::Reverse, GameOfLife::reverse);
+                    None
+                }
+                Some(Action::Edit) => {
+                    editor.run(so, &mut game, &mut camera, &mut history)?;
+                    None
+                }
+                Some(Action::Rotate) => Some(Transform::Rotate(1)),
+                Some(Action::FlipHorizontal) => Some(Transform::FlipHorizontal),
+                Some(Action::FlipVertical) => Some(Transform::FlipVertical),
+                Some(Action::Transpose) => Some(Transform::Transpose),
+                Some(Action::Scale) => Some(Transform::Scale(2)),
+                None => None,
+            };
+            //errors only mean the pattern can't be transformed with this rule or is too large.
+            //Puzzles are played as they were set up
+            if let (Some(t), None) = (transform, &game.puzzle) {
+                history
+                    .record(&mut game, Command::Transform, |g| g.transform(t))
+                    .unwrap_or(());
             }
+        }
 
-            execute!(
-                stdout,
-                cursor::MoveToNextLine(1),
-                Print(format!("died at generation {}", &count)),
-                cursor::MoveToNextLine(1)
-            );
+        if quit {
+            break;
         }
-        Err(e) => {
-            disable_raw_mode().unwrap();
-            execute!(stdout, cursor::MoveToNextLine(1));
-            eprintln!("{:?}", e);
-            execute!(stdout, cursor::MoveToNextLine(1));
+
+        //Display the new generation
+        //the number of lines of the hud changes with the size of the terminal and the keys shown
+        let size = terminal::size().map_err(Error::Terminal)?;
+        queue!(so, Clear(ClearType::All), cursor::MoveTo(0, 0));
+        let lines = hud(so, &game, speed, paused, &options.config.keys, size.0);
+        camera.fit(size.0, size.1, lines);
+        game.show_in_camera(so, &camera);
+
+        if game.puzzle_result(false).is_some() {
+            break;
         }
     }
 
-    //end using crossterm
-    disable_raw_mode().unwrap();
+    let end = match game.puzzle_result(true) {
+        Some(true) => "puzzle solved",
+        Some(false) => "puzzle failed",
+        None => "died",
+    };
+    Ok(format!("{} at generation {}", end, &game.generation))
 }
 
 #[derive(Clone)]
 struct CellRow {
     row: Vec<Cell>,
     y: isize,
+    //state of the cells outside of the row, only alive for one dimensional rules where empty
+    //neighbourhoods come alive
+    background: bool,
 }
 impl CellRow {
     //base_x is the x of the first cell (based on the one of others)
     fn new(y: isize, base_x: isize) -> CellRow {
         CellRow {
             row: vec![Cell::new(base_x, false)],
-            y: y,
+            y,
+            background: false,
         }
     }
 
@@ -173,28 +297,22 @@ impl CellRow {
     }
 }
 
+//a set so that large neighbourhoods don't make every push scan all the coordinates
 #[derive(Clone)]
 struct UniqueCoordinates {
-    coords: Vec<(isize, isize)>,
+    coords: HashSet<(isize, isize)>,
 }
 impl UniqueCoordinates {
-    fn push(&mut self, coords: (isize, isize)) {
-        let can_push = !self
-            .coords
-            .iter_mut()
-            .any(|c| c.0 == coords.0 && c.1 == coords.1);
-        if can_push {
-            self.coords.push(coords);
+    fn new() -> Self {
+        UniqueCoordinates {
+            coords: HashSet::new(),
         }
     }
+    fn push(&mut self, coords: (isize, isize)) {
+        self.coords.insert(coords);
+    }
     fn remove(&mut self, coords: (isize, isize)) {
-        let index = self
-            .coords
-            .iter()
-            .position(|c| c.0 == coords.0 && c.1 == coords.1);
-        if let Some(i) = index {
-            self.coords.remove(i);
-        }
+        self.coords.remove(&coords);
     }
 }
 
@@ -202,75 +320,112 @@ impl UniqueCoordinates {
 struct GameGrid {
     grid: Vec<CellRow>,
     alive_cells: UniqueCoordinates,
+    //cells changed while the history records a command
+    journal: Journal,
 }
 impl GameGrid {
     fn new() -> Self {
         GameGrid {
             grid: vec![CellRow::new(0, 0)],
-            alive_cells: UniqueCoordinates { coords: vec![] },
+            alive_cells: UniqueCoordinates::new(),
+            journal: Journal::default(),
         }
     }
-    fn get_neighbours_coords(&mut self, x: isize, y: isize) -> Vec<(isize, isize)> {
+    fn get_neighbours_coords(&mut self, x: isize, y: isize, rule: &Rule) -> Vec<(isize, isize)> {
         let mut r: Vec<(isize, isize)> = vec![];
 
-        for j in -1..=1 {
-            for i in -1..=1 {
-                if i != 0 || j != 0 {
-                    if let Some(_) = self.get_cell(x + i, y + j) {
-                        r.push((x + i, y + j));
-                    }
-                }
+        for (i, j) in rule.neighbourhood(x, y).iter() {
+            if self.get_cell(x + i, y + j).is_some() {
+                r.push((x + i, y + j));
             }
         }
 
         r
     }
-    fn count_neighbours(&mut self, x: isize, y: isize) -> u8 {
-        let mut count: u8 = 0;
+    //bitmask or count of the alive neighbours depending on the rule
+    fn count_neighbours(&mut self, x: isize, y: isize, rule: &Rule) -> u32 {
+        let mut res: u32 = 0;
 
-        for j in -1..=1 {
-            for i in -1..=1 {
-                if i != 0 || j != 0 {
-                    if let Some(c) = self.get_cell(x + i, y + j) {
-                        if c.is_alive {
-                            count += 1;
-                        }
+        for (bit, (i, j)) in rule.neighbourhood(x, y).iter().enumerate() {
+            if let Some(c) = self.get_cell(x + i, y + j) {
+                if c.is_alive {
+                    if rule.uses_bitmask() {
+                        res |= 1 << bit;
+                    } else {
+                        res += 1;
                     }
                 }
             }
         }
 
-        count
+        res
+    }
+
+    //colour the cell at x, y would have if it was born
+    fn birth_colour(&mut self, x: isize, y: isize, rule: &Rule) -> u8 {
+        let mut parents = vec![];
+        for (i, j) in rule.neighbourhood(x, y).iter() {
+            if let Some(c) = self.get_cell(x + i, y + j) {
+                if c.is_alive {
+                    parents.push(c.colour);
+                }
+            }
+        }
+        rule.birth_colour(&parents)
     }
 
-    //whenever there is an alive cell on an edge expend the edge by 1 (row or cell for each row)
-    //+when edge row or column is dead and so is its neighbour remove it
+    //keep `radius` dead rows and columns around the alive cells so that every cell that can be
+    //born next generation is in the grid
     //To call right after next (or at the end of it)
     //TODO try to do similar function that updates only part of edges?
-    fn update_edges(&mut self) {
-        if self.is_alive_left() {
+    fn update_edges(&mut self, radius: isize) {
+        if self.alive_cells.coords.is_empty() {
+            return;
+        }
+        let radius = radius as usize;
+
+        let width = self.grid[0].row.len();
+        let mut left = (0..width).take_while(|i| !self.is_alive_column(*i)).count();
+        let mut right = (0..width).rev().take_while(|i| !self.is_alive_column(*i)).count();
+        let height = self.grid.len();
+        let mut top = (0..height).take_while(|i| !self.is_alive_row(*i)).count();
+        let mut bottom = (0..height).rev().take_while(|i| !self.is_alive_row(*i)).count();
+
+        while left < radius {
             for r in self.grid.iter_mut() {
                 r.prepend_cell(false);
             }
-        } else if !self.is_alive_column(1) {
+            left += 1;
+        }
+        while left > radius {
             self.remove_first_column();
+            left -= 1;
         }
-        if self.is_alive_right() {
+        while right < radius {
             for r in self.grid.iter_mut() {
                 r.append_cell(false);
             }
-        } else if !self.is_alive_column(self.grid[0].row.len() - 2) {
+            right += 1;
+        }
+        while right > radius {
             self.remove_last_column();
+            right -= 1;
         }
-        if self.is_alive_bottom() {
+        while bottom < radius {
             self.append_row_and_fill();
-        } else if !self.is_alive_row(self.grid.len() - 2) {
+            bottom += 1;
+        }
+        while bottom > radius {
             self.remove_last_row();
+            bottom -= 1;
         }
-        if self.is_alive_top() {
+        while top < radius {
             self.prepend_row_and_fill();
-        } else if !self.is_alive_row(1) {
+            top += 1;
+        }
+        while top > radius {
             self.remove_first_row();
+            top -= 1;
         }
     }
 
@@ -278,25 +433,54 @@ impl GameGrid {
         self.get_row(y)?.get_cell(x)
     }
 
+    fn is_alive(&self, x: isize, y: isize) -> bool {
+        self.alive_cells.coords.contains(&(x, y))
+    }
+
+    //grows the grid when the cell is outside of it
+    fn set_cell(&mut self, x: isize, y: isize, alive: bool) {
+        while y < self.grid.first().unwrap().y {
+            self.prepend_row_and_fill();
+        }
+        while y > self.grid.last().unwrap().y {
+            self.append_row_and_fill();
+        }
+        while x < self.grid[0].row.first().unwrap().x {
+            for r in self.grid.iter_mut() {
+                r.prepend_cell(false);
+            }
+        }
+        while x > self.grid[0].row.last().unwrap().x {
+            for r in self.grid.iter_mut() {
+                r.append_cell(false);
+            }
+        }
+
+        let c = self.get_cell(x, y).unwrap();
+        let before = c.is_alive.then_some(c.colour);
+        c.is_alive = alive;
+        self.journal.note((x, y), before);
+        if alive {
+            self.alive_cells.push((x, y));
+        } else {
+            self.alive_cells.remove((x, y));
+        }
+    }
+
+    fn clear(&mut self) {
+        let alive: Vec<(isize, isize)> = self.alive_cells.coords.iter().cloned().collect();
+        for (x, y) in alive {
+            self.set_cell(x, y, false);
+        }
+    }
+
     //input is the real index the artificial one
     fn is_alive_row(&self, index: usize) -> bool {
         self.grid.get(index).unwrap().row.iter().any(|c| c.is_alive)
     }
-    fn is_alive_bottom(&self) -> bool {
-        self.grid.last().unwrap().row.iter().any(|c| c.is_alive)
-    }
-    fn is_alive_top(&self) -> bool {
-        self.grid.first().unwrap().row.iter().any(|c| c.is_alive)
-    }
     fn is_alive_column(&self, index: usize) -> bool {
         self.grid.iter().any(|r| r.row.get(index).unwrap().is_alive)
     }
-    fn is_alive_left(&self) -> bool {
-        self.grid.iter().any(|r| r.row.first().unwrap().is_alive)
-    }
-    fn is_alive_right(&self) -> bool {
-        self.grid.iter().any(|r| r.row.last().unwrap().is_alive)
-    }
 
     //add new row to the end
     fn append_row(&mut self) {
@@ -390,33 +574,44 @@ impl GameGrid {
         }
     }
 
-    fn next(&mut self) -> bool {
+    fn next(&mut self, rule: &Rule, chance: &mut Chance) -> bool {
         let mut clo = self.clone();
 
         let ac = self.alive_cells.clone();
-        let mut change_cells = UniqueCoordinates { coords: vec![] };
+        let mut change_cells = UniqueCoordinates::new();
 
         //get cells coords to change
         for coords in ac.coords.iter() {
             change_cells.push((coords.0, coords.1));
-            for n in self.get_neighbours_coords(coords.0, coords.1) {
+            for n in self.get_neighbours_coords(coords.0, coords.1, rule) {
                 change_cells.push((n.0, n.1));
             }
         }
 
         //update the cells
         for co in change_cells.coords.iter() {
+            let neighbours = clo.count_neighbours(co.0, co.1, rule);
+            let colour = match rule.colours {
+                1 => 0,
+                _ => clo.birth_colour(co.0, co.1, rule),
+            };
             let c = self.get_cell(co.0, co.1).unwrap();
-            c.update(clo.count_neighbours(co.0, co.1));
+            c.update(neighbours, colour);
         }
 
+        //same order every run so that a seed always gives the same result
+        let mut ordered: Vec<&(isize, isize)> = change_cells.coords.iter().collect();
+        ordered.sort();
+
         let mut changed = false;
         //go to next cells
-        for co in change_cells.coords.iter() {
+        for co in ordered {
             let c = self.get_cell(co.0, co.1).unwrap();
             let was_alive = c.is_alive;
-            if c.next() {
+            let before = was_alive.then_some(c.colour);
+            if c.next(rule, chance) {
                 changed = true;
+                self.journal.note(*co, before);
                 if was_alive {
                     self.alive_cells.remove(*co);
                 } else {
@@ -424,16 +619,73 @@ impl GameGrid {
                 }
             }
         }
+        changed |= self.add_noise(chance);
 
         // makes sure next generation will have enough space
-        self.update_edges();
+        self.update_edges(rule.radius());
 
         changed
     }
 
-    fn add_text(&mut self, text: &str, row_min: usize, col_min: usize) {
+    //flip every cell of the grid with the noise probability
+    fn add_noise(&mut self, chance: &mut Chance) -> bool {
+        if chance.noise <= 0.0 {
+            return false;
+        }
+
+        let mut changed = false;
+        for r in self.grid.iter_mut() {
+            for c in r.row.iter_mut() {
+                if chance.happens(chance.noise) {
+                    self.journal.note((c.x, r.y), c.is_alive.then_some(c.colour));
+                    c.is_alive = !c.is_alive;
+                    changed = true;
+                    if c.is_alive {
+                        self.alive_cells.push((c.x, r.y));
+                    } else {
+                        self.alive_cells.remove((c.x, r.y));
+                    }
+                }
+            }
+        }
+        changed
+    }
+
+    //pattern of a .gol or .rle file, with the rule given by the file if any
+    fn read(path: &str) -> Result<(GameGrid, Option<Rule>), Error> {
+        let contents = library::read_to_string(path)?;
+        GameGrid::parse(&contents, path.ends_with(".rle")).map_err(|e| e.in_file(path))
+    }
+
+    fn parse(contents: &str, is_rle: bool) -> Result<(GameGrid, Option<Rule>), Error> {
+        let mut contents = contents.to_string();
+        let mut rule = None;
+
+        //LifeWiki patterns are run length encoded and give their rule in the header
+        if is_rle {
+            let pattern = rle::parse(&contents)?;
+            if let Some(r) = pattern.rule {
+                rule = Some(Rule::parse(&r)?);
+            }
+            contents = pattern.text;
+        } else {
+            for (line_index, line) in contents.lines().enumerate() {
+                check_gol_line(line, line_index)?;
+            }
+        }
+
+        let mut g = GameGrid::new();
+        g.add_text(&contents, 0, 0);
+        g.fix_grid_size();
+        g.init_alive_cells();
+        Ok((g, rule))
+    }
+
+    fn add_text(&mut self, text: &str, row_min: usize, _col_min: usize) {
         //TODO add way to have a minimum of columns
-        let n_row = text.trim().chars().filter(|x| *x == '\n').count();
+        //only the empty lines around the pattern are left out, spaces being dead cells
+        let text = text.trim_matches(|c| c == '\n' || c == '\r');
+        let n_row = text.chars().filter(|x| *x == '\n').count();
         let mut row_diff = 0;
         if row_min > n_row {
             row_diff = row_min - n_row;
@@ -448,69 +700,183 @@ impl GameGrid {
         }
 
         let mut line_count = line_start;
-        for c in text.trim().chars() {
+        for c in text.chars() {
             if c == '\n' {
                 line_count += 1;
                 if line_count >= self.grid.len() {
                     self.append_row();
                 }
             } else {
-                //add cell which is alive if char in str is 'a'
-                self.get_row(line_count as isize)
-                    .unwrap()
-                    .append_cell(c == 'a');
+                //add cell which is alive if char in str is 'a', or 'b', 'c' and 'd' for the
+                //other colours of multi-colour rules
+                let alive = ('a'..='d').contains(&c);
+                let r = self.get_row(line_count as isize).unwrap();
+                r.append_cell(alive);
+                if alive {
+                    r.row.last_mut().unwrap().colour = c as u8 - b'a';
+                }
             }
         }
     }
 }
 
+//cells of .gol files are alive with 'a' (or 'b', 'c' and 'd' for the other colours of
+//multi-colour rules) and dead with '-', '_', '.' or a space
+fn check_gol_line(line: &str, line_index: usize) -> Result<(), Error> {
+    match line
+        .chars()
+        .enumerate()
+        .find(|(_, c)| !('a'..='d').contains(c) && !"-_. \r".contains(*c))
+    {
+        Some((column, c)) => Err(Error::UnknownCharacter {
+            file: None,
+            line: line_index + 1,
+            column: column + 1,
+            character: c,
+        }),
+        None => Ok(()),
+    }
+}
+
 pub struct GameOfLife {
     game_grid: GameGrid,
+    rule: Rule,
+    generation: isize,
+    //previous generation, only kept in second-order mode
+    previous: Option<GameGrid>,
+    backwards: bool,
+    chance: Chance,
+    puzzle: Option<Puzzle>,
+    //the error is shown in the hud, the pattern still runs
+    metadata: Result<Metadata, String>,
+    theme: Theme,
+    topology: Topology,
 }
 impl GameOfLife {
-    fn init(path: &str) -> Result<GameOfLife, io::Error> {
-        let contents = fs::read_to_string(path)?;
-        let mut g = GameGrid::new();
+    fn init(path: &str) -> Result<GameOfLife, Error> {
+        let mut game = if path.ends_with(".scene") {
+            scene::load(path)?
+        } else {
+            let (g, rule) = GameGrid::read(path)?;
+            GameOfLife::new(g, rule.unwrap_or_else(Rule::conway))
+        };
+        game.metadata = Metadata::read(path).map_err(|e| e.to_string());
+        Ok(game)
+    }
+
+    //pattern read from the standard input, in either format
+    fn from_text(contents: &str) -> Result<GameOfLife, Error> {
+        let is_rle = rle::is_rle(contents);
+        let (g, rule) = GameGrid::parse(contents, is_rle)?;
+        let mut game = GameOfLife::new(g, rule.unwrap_or_else(Rule::conway));
+        if is_rle {
+            game.metadata = Ok(Metadata::from_rle(contents));
+        }
+        Ok(game)
+    }
 
-        g.add_text(&contents, 0, 0);
+    fn new(mut g: GameGrid, rule: Rule) -> GameOfLife {
         g.fix_grid_size();
         g.init_alive_cells();
 
-        Ok(GameOfLife { game_grid: g })
+        let mut game = GameOfLife {
+            game_grid: g,
+            rule: Rule::conway(),
+            generation: 0,
+            previous: None,
+            backwards: false,
+            chance: Chance::deterministic(),
+            puzzle: None,
+            metadata: Ok(Metadata::default()),
+            theme: Theme::default(),
+            topology: Topology::Plane,
+        };
+        game.set_rule(rule);
+        game
+    }
+
+    //one dimensional automaton starting from a single alive cell
+    fn elementary(n: u8) -> GameOfLife {
+        let mut g = GameGrid::new();
+        g.grid[0].row[0].is_alive = true;
+
+        GameOfLife::new(g, Rule::elementary(n))
     }
 
-    fn from_word(s: &str) -> Result<GameOfLife, io::Error> {
-        //Read the word
-        let base_dir = "./letters/".to_string();
+    //every line of s is drawn below the previous one
+    fn from_word(s: &str, font: &Font, layout: &Layout) -> Result<GameOfLife, Error> {
         let mut g = GameGrid::new();
+        g.add_text(&font.render(s, layout), 16, 0);
 
-        //let spaced_s = "  ".to_string() + s + "  ";
-        for i in 0..s.len() {
-            //Get File corresponding to letter
-            let mut c = &s[i..i + 1];
-            if c == " " {
-                c = "empty";
-            }
-            let file: String = "".to_string() + &base_dir + c + ".gol";
-            let letter = std::fs::read_to_string(file)?;
+        Ok(GameOfLife::new(g, Rule::conway()))
+    }
 
-            g.add_text(&letter, 16, 0);
+    fn set_rule(&mut self, rule: Rule) {
+        if rule.elementary_number().is_some() {
+            self.game_grid.init_elementary();
+        } else {
+            // cells on the border of the pattern must have room for their neighbours
+            self.game_grid.update_edges(rule.radius());
         }
-        g.fix_grid_size();
-        g.init_alive_cells();
-
-        Ok(GameOfLife { game_grid: g })
+        self.rule = rule;
     }
 
     fn next(&mut self) -> bool {
-        self.game_grid.next()
+        if let Topology::Torus(width, height) = self.topology {
+            return self.next_on_torus(width, height);
+        }
+        let changed = if let Some(table) = self.rule.margolus_table(self.generation) {
+            self.game_grid.next_margolus(&table, self.generation)
+        } else if self.rule.elementary_number().is_some() {
+            self.game_grid.next_elementary(&self.rule)
+        } else if self.is_second_order() {
+            self.next_second_order()
+        } else {
+            self.game_grid.next(&self.rule, &mut self.chance)
+        };
+        self.generation += if self.backwards { -1 } else { 1 };
+        changed
+    }
+
+    //rule and mode shown in the hud
+    fn description(&self) -> String {
+        let mut res = match (self.is_second_order(), self.backwards) {
+            (false, _) => self.rule.name.clone(),
+            (true, false) => format!("{} (second-order)", self.rule.name),
+            (true, true) => format!("{} (second-order, backwards)", self.rule.name),
+        };
+        if let Topology::Torus(width, height) = self.topology {
+            res += &format!("; Torus: {}x{}", width, height);
+        }
+        if !self.chance.is_deterministic() {
+            res += &format!(
+                "; Birth: {}%; Survival: {}%; Noise: {}%; Seed: {}",
+                self.chance.birth * 100.0,
+                self.chance.survival * 100.0,
+                self.chance.noise * 100.0,
+                self.chance.seed
+            );
+        }
+        res
+    }
+
+    //scroll down with the space-time diagram of one dimensional automata, unless the user moved
+    //away from the newest generation
+    fn follow_generations(&self, camera: &mut ViewRect) {
+        if self.rule.elementary_number().is_none() {
+            return;
+        }
+        let newest = self.game_grid.grid.last().unwrap().y;
+        if newest == camera.y + camera.y_len {
+            camera.move_down(1);
+        }
     }
 
     #[allow(unused_must_use)]
     fn show_in_camera(&mut self, so: &mut Stdout, camera: &ViewRect) {
         //, rect: ViewRect) {
-        let border_style = style::PrintStyledContent("█".dark_green());
-        let alive_style = style::PrintStyledContent("█".dark_cyan());
+        let theme = self.theme.clone();
+        let border_style = style::PrintStyledContent("█".with(theme.border));
         //TOP BORDER
         queue!(so, cursor::MoveToNextLine(1));
         for _ in 0..camera.x_len + 2 {
@@ -521,19 +887,72 @@ impl GameOfLife {
             //LEFT BORDER
             queue!(so, cursor::MoveToNextLine(1), &border_style);
             //CONTENT
-            for x in camera.x..camera.x + camera.x_len {
-                let cell_option = self.game_grid.get_cell(x, y);
-                match cell_option {
-                    Some(c) => {
-                        match c.is_alive {
-                            true => queue!(so, &alive_style),
-                            false => queue!(so, style::PrintStyledContent("+".magenta())), //cursor::MoveRight(1)),
+            match self.rule.lattice {
+                Lattice::Square => {
+                    let elementary = self.rule.elementary_number().is_some();
+                    for x in camera.x..camera.x + camera.x_len {
+                        //older generations of one dimensional automata are shorter
+                        let background = match elementary {
+                            true => self.game_grid.background_at(x, y),
+                            false => None,
+                        };
+                        let cell_option = self.game_grid.get_cell(x, y);
+                        match cell_option {
+                            Some(c) => {
+                                match c.is_alive {
+                                    true => queue!(
+                                        so,
+                                        style::PrintStyledContent("█".with(theme.colour(c.colour)))
+                                    ),
+                                    false => queue!(so, style::PrintStyledContent("+".with(theme.dead))), //cursor::MoveRight(1)),
+                                };
+                            }
+                            None => {
+                                let outside = match background {
+                                    Some(true) => "█".with(theme.colour(0)),
+                                    Some(false) => "+".with(theme.dead),
+                                    None => "-".with(theme.outside),
+                                };
+                                queue!(so, style::PrintStyledContent(outside));
+                            }
+                        };
+                    }
+                }
+                Lattice::Hexagonal => {
+                    //cells are two characters wide so that odd rows can be shifted by half a cell
+                    let odd = y.rem_euclid(2) == 1;
+                    let n_cells = (camera.x_len - 1) / 2;
+                    if odd {
+                        queue!(so, Print(" "));
+                    }
+                    for x in camera.x..camera.x + n_cells {
+                        match self.game_grid.get_cell(x, y) {
+                            Some(c) if c.is_alive => {
+                                queue!(so, style::PrintStyledContent("██".with(theme.colour(c.colour))))
+                            }
+                            Some(_) => queue!(so, style::PrintStyledContent("+ ".with(theme.dead))),
+                            None => queue!(so, style::PrintStyledContent("- ".with(theme.outside))),
                         };
                     }
-                    None => {
-                        queue!(so, style::PrintStyledContent("-".dark_red()));
+                    let used = n_cells.max(0) * 2 + odd as isize;
+                    queue!(so, Print(" ".repeat((camera.x_len - used).max(0) as usize)));
+                }
+                Lattice::Triangular => {
+                    for x in camera.x..camera.x + camera.x_len {
+                        let up = (x + y).rem_euclid(2) == 0;
+                        match self.game_grid.get_cell(x, y) {
+                            Some(c) if c.is_alive => {
+                                let t = if up { "▲" } else { "▼" };
+                                queue!(so, style::PrintStyledContent(t.with(theme.colour(c.colour))))
+                            }
+                            Some(_) => {
+                                let t = if up { "△" } else { "▽" };
+                                queue!(so, style::PrintStyledContent(t.with(theme.dead)))
+                            }
+                            None => queue!(so, style::PrintStyledContent("-".with(theme.outside))),
+                        };
                     }
-                };
+                }
             }
             //RIGHT BORDER
             queue!(so, &border_style);
@@ -548,40 +967,49 @@ impl GameOfLife {
     }
 }
 
-
 #[derive(Debug, Clone)]
 struct Cell {
     is_alive: bool,
-    neighbours: u8,
+    //only used by multi-colour rules
+    colour: u8,
+    birth_colour: u8,
+    //bitmask or count of the alive neighbours, see Rule::uses_bitmask
+    neighbours: u32,
     x: isize,
     //y: isize
 }
 impl Cell {
     fn new(x: isize, is_alive: bool) -> Cell {
         Cell {
-            is_alive: is_alive,
+            is_alive,
+            colour: 0,
+            birth_colour: 0,
             neighbours: 0,
-            x: x,
+            x,
             //y: y,
         }
     }
 
-    fn update(&mut self, neighbours: u8) {
+    fn update(&mut self, neighbours: u32, birth_colour: u8) {
         self.neighbours = neighbours;
+        self.birth_colour = birth_colour;
     }
 
-    fn next(&mut self) -> bool {
-        if self.is_alive {
-            if self.neighbours <= 1 || self.neighbours >= 4 {
-                self.is_alive = false;
-                return true;
-            }
-        } else {
-            if self.neighbours == 3 {
-                self.is_alive = true;
-                return true;
-            }
+    fn next(&mut self, rule: &Rule, chance: &mut Chance) -> bool {
+        let mut alive = rule.next_state(self.is_alive, self.neighbours);
+        if alive {
+            let probability = if self.is_alive {
+                chance.survival
+            } else {
+                chance.birth
+            };
+            alive = chance.happens(probability);
         }
-        false
+        let changed = alive != self.is_alive;
+        if alive && !self.is_alive {
+            self.colour = self.birth_colour;
+        }
+        self.is_alive = alive;
+        changed
     }
 }
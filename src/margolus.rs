@@ -0,0 +1,53 @@
+use crate::{GameGrid, UniqueCoordinates};
+
+//cells of a block in the order of the bits of its state
+const BLOCK: [(isize, isize); 4] = [(0, 0), (1, 0), (0, 1), (1, 1)];
+
+//block automata split the grid in 2x2 blocks, the blocks are shifted by one cell every other
+//generation
+impl GameGrid {
+    pub fn next_margolus(&mut self, table: &[u8; 16], generation: isize) -> bool {
+        let offset = generation.rem_euclid(2);
+
+        //top left cell of every block with an alive cell, empty blocks stay empty
+        let mut blocks = UniqueCoordinates::new();
+        for (x, y) in self.alive_cells.coords.iter() {
+            let bx = (x - offset).div_euclid(2) * 2 + offset;
+            let by = (y - offset).div_euclid(2) * 2 + offset;
+            blocks.push((bx, by));
+        }
+
+        let mut changed = false;
+        for (bx, by) in blocks.coords.iter() {
+            let mut state = 0;
+            for (bit, (i, j)) in BLOCK.iter().enumerate() {
+                if self.get_cell(bx + i, by + j).unwrap().is_alive {
+                    state |= 1 << bit;
+                }
+            }
+
+            let new_state = table[state as usize];
+            if new_state == state {
+                continue;
+            }
+            changed = true;
+            for (bit, (i, j)) in BLOCK.iter().enumerate() {
+                let alive = new_state & (1 << bit) != 0;
+                let c = self.get_cell(bx + i, by + j).unwrap();
+                let before = c.is_alive.then_some(c.colour);
+                c.is_alive = alive;
+                self.journal.note((bx + i, by + j), before);
+                if alive {
+                    self.alive_cells.push((bx + i, by + j));
+                } else {
+                    self.alive_cells.remove((bx + i, by + j));
+                }
+            }
+        }
+
+        // makes sure every block of the next generation is in the grid
+        self.update_edges(1);
+
+        changed
+    }
+}
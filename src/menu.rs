@@ -1,73 +1,213 @@
+use crate::browser::browse;
+use crate::cli::{Options, Start};
+use crate::error::Error;
+use crate::keys::{Action, Keys};
+use crate::puzzle;
+use crate::rule::{Lattice, Rule};
+use crate::topology::Topology;
+use crate::tty::read_event;
+use crate::versus;
 use crate::GameOfLife;
 
-use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyModifiers};
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
 use crossterm::style::Print;
 use crossterm::terminal::{Clear, ClearType};
 use crossterm::{cursor, execute, queue};
 
-use std::fs;
-use std::io::{self, Stdout};
+use std::io::Stdout;
 
-
-pub fn game_selection(so: &mut Stdout) -> Result<GameOfLife, io::Error> {
-    queue!(so, Clear(ClearType::All), cursor::MoveTo(0, 0));
-    //Show options menu
-    queue!(
-        so,
-        Print("Welcome to the Conway's Game of Life!"),
-        cursor::MoveToNextLine(1)
-    );
-    queue!(
-        so,
-        Print("Where do you want to start?"),
-        cursor::MoveToNextLine(1)
-    );
-    queue!(so, Print("'q' to quit"), cursor::MoveToNextLine(1));
-    queue!(so, Print("1 : Load an example"), cursor::MoveToNextLine(1));
-    queue!(so, Print("2 : Load your file"), cursor::MoveToNextLine(1));
-    execute!(
-        so,
-        Print("3 : Generate from word"),
-        cursor::MoveToNextLine(1)
-    );
-
-    match event::read().unwrap() {
-        Event::Key(KeyEvent {
-            code: KeyCode::Char('1'),
-            modifiers: KeyModifiers::NONE,
-        }) => {
-            let gol_file = file_selection(so);
-            return Ok(GameOfLife::init(&gol_file)?);
+//None when the user quits. The errors of the chosen pattern are shown above the menu, only the
+//terminal's errors and the ones of the pattern given on the command line are returned
+#[allow(unused_must_use)]
+pub fn game_selection(so: &mut Stdout, options: &Options) -> Result<Option<GameOfLife>, Error> {
+    if let Some(start) = &options.start {
+        let mut game = start_game(start, options)?;
+        let overridden = options.rule.is_some()
+            || !options.transforms.is_empty()
+            || options.topology != Topology::Plane
+            || !options.chance.is_deterministic();
+        if game.puzzle.is_some() && overridden {
+            return Err(Error::invalid(
+                "levels can't be given a rule, a transformation, a topology or probabilities",
+            ));
         }
-        Event::Key(KeyEvent {
-            code: KeyCode::Char('2'),
-            modifiers: KeyModifiers::NONE,
-        }) => {
-            let path = get_input(so, "Enter file path");
-            return Ok(GameOfLife::init(&path)?);
+        prepare(so, &mut game, options.rule.clone(), false, options)?;
+        return Ok(Some(game));
+    }
+
+    //rule chosen by the user, replacing the one of the pattern
+    let mut rule: Option<Rule> = options.rule.clone();
+    let mut second_order = false;
+    //error of the last choice
+    let mut error: Option<Error> = None;
+
+    loop {
+        queue!(so, Clear(ClearType::All), cursor::MoveTo(0, 0));
+        //Show options menu
+        queue!(
+            so,
+            Print("Welcome to the Conway's Game of Life!"),
+            cursor::MoveToNextLine(1)
+        );
+        queue!(
+            so,
+            Print("Where do you want to start?"),
+            cursor::MoveToNextLine(1)
+        );
+        queue!(so, Print("'q' to quit"), cursor::MoveToNextLine(1));
+        queue!(so, Print("1 : Load an example"), cursor::MoveToNextLine(1));
+        queue!(so, Print("2 : Load your file"), cursor::MoveToNextLine(1));
+        queue!(so, Print("3 : Generate from word"), cursor::MoveToNextLine(1));
+        let rule_name = match &rule {
+            Some(r) => r.name.clone(),
+            None => "from the pattern".to_string(),
+        };
+        queue!(
+            so,
+            Print(format!("4 : Change the rule (current: {})", rule_name)),
+            cursor::MoveToNextLine(1)
+        );
+        queue!(
+            so,
+            Print("5 : One-dimensional cellular automaton"),
+            cursor::MoveToNextLine(1)
+        );
+        queue!(
+            so,
+            Print(format!(
+                "6 : Second-order reversible mode (current: {})",
+                if second_order { "on" } else { "off" }
+            )),
+            cursor::MoveToNextLine(1)
+        );
+        queue!(
+            so,
+            Print("7 : Two players game (Immigration)"),
+            cursor::MoveToNextLine(1)
+        );
+        queue!(so, Print("8 : Puzzles"), cursor::MoveToNextLine(1));
+        if let Some(e) = error.take() {
+            queue!(so, cursor::MoveToNextLine(1), Print(format!("Error: {}", e)));
         }
-        Event::Key(KeyEvent {
-            code: KeyCode::Char('3'),
-            modifiers: KeyModifiers::NONE,
-        }) => {
-            let word = get_input(so, "Enter a text to use");
-            return Ok(GameOfLife::from_word(&word)?);
+        execute!(so, cursor::MoveToNextLine(1));
+
+        let code = match read_event()? {
+            Event::Key(KeyEvent {
+                code,
+                modifiers: KeyModifiers::NONE,
+            }) => code,
+            _ => continue,
+        };
+        let game = match code {
+            KeyCode::Char('q') => return Ok(None),
+            KeyCode::Char('1') => match browse(so, &options.library.patterns) {
+                Ok(Some(gol_file)) => GameOfLife::init(&gol_file),
+                Ok(None) => continue,
+                Err(e) => Err(e),
+            },
+            KeyCode::Char('2') => {
+                let path = get_input(so, "Enter file path")?;
+                GameOfLife::init(&path)
+            }
+            KeyCode::Char('3') => {
+                let text = get_lines(so, "Enter a text to use")?;
+                GameOfLife::from_word(&text, &options.font, &options.layout)
+            }
+            KeyCode::Char('4') => {
+                let r = get_input(
+                    so,
+                    "Enter a rule (B3/S23, B2-a/S12, B2/S34H for hexagonal, B4/S345T for triangular, R5,C0,M1,S34..58,B34..45,NM, W30 for one-dimensional, Immigration, QuadLife)",
+                )?;
+                match Rule::parse(&r) {
+                    Ok(r) => rule = Some(r),
+                    Err(e) => error = Some(e),
+                }
+                continue;
+            }
+            KeyCode::Char('5') => {
+                let n = get_input(so, "Enter Wolfram's rule number (0-255), like 30 or 110")?;
+                n.trim()
+                    .parse()
+                    .map(GameOfLife::elementary)
+                    .map_err(|_| Error::invalid("the rule must be between 0 and 255"))
+            }
+            KeyCode::Char('6') => {
+                second_order = !second_order;
+                continue;
+            }
+            KeyCode::Char('7') => {
+                versus::play(so, &options.config.theme)?;
+                continue;
+            }
+            KeyCode::Char('8') => match browse(so, &options.library.levels) {
+                Ok(Some(level)) => puzzle::load(&level),
+                Ok(None) => continue,
+                Err(e) => Err(e),
+            },
+            _ => continue,
+        };
+
+        let game = game.and_then(|mut g| {
+            prepare(so, &mut g, rule.clone(), second_order, options)?;
+            Ok(g)
+        });
+        match game {
+            Ok(g) => return Ok(Some(g)),
+            //they can't be shown
+            Err(e @ Error::Terminal(_)) => return Err(e),
+            Err(e) => error = Some(e),
         }
-        _ => (),
     }
+}
 
-    //create game
-    Err(io::Error::new(io::ErrorKind::Other, "No Option Selected"))
+//the choices of the menu and of the command line are applied, then the player changes the cells
+//of puzzles. Levels keep their own rule, lattice and plane so that they can't be solved for free
+fn prepare(
+    so: &mut Stdout,
+    game: &mut GameOfLife,
+    rule: Option<Rule>,
+    second_order: bool,
+    options: &Options,
+) -> Result<(), Error> {
+    if game.puzzle.is_some() {
+        return puzzle::edit(so, game);
+    }
+    if let Some(r) = rule {
+        game.set_rule(r);
+    }
+    game.set_second_order(second_order)?;
+    for t in options.transforms.iter() {
+        game.transform(*t)?;
+    }
+    game.set_topology(options.topology)?;
+    puzzle::edit(so, game)
 }
 
-fn get_input(so: &mut Stdout, instruction: &str) -> String {
+//pattern given on the command line
+fn start_game(start: &Start, options: &Options) -> Result<GameOfLife, Error> {
+    match start {
+        Start::File(path) if path.ends_with(".level") => puzzle::load(path),
+        Start::File(path) => GameOfLife::init(path),
+        Start::Stdin(contents) => GameOfLife::from_text(contents),
+        Start::Word(text) => GameOfLife::from_word(text, &options.font, &options.layout),
+        Start::Random {
+            width,
+            height,
+            density,
+            seed,
+        } => Ok(GameOfLife::random(*width, *height, *density, *seed)),
+    }
+}
+
+#[allow(unused_must_use)]
+pub fn get_input(so: &mut Stdout, instruction: &str) -> Result<String, Error> {
     let mut res = String::new();
     queue!(so, Clear(ClearType::All), cursor::MoveTo(0, 0));
     queue!(so, Print(instruction));
     execute!(so, cursor::MoveToNextLine(1));
 
     loop {
-        match event::read().unwrap() {
+        match read_event()? {
             Event::Key(KeyEvent {
                 code: KeyCode::Enter,
                 modifiers: KeyModifiers::NONE,
@@ -78,7 +218,7 @@ fn get_input(so: &mut Stdout, instruction: &str) -> String {
                 code: KeyCode::Backspace,
                 modifiers: KeyModifiers::NONE,
             }) => {
-                res = res[0..res.len() - 1].to_string();
+                res.pop();
                 execute!(
                     so,
                     Clear(ClearType::CurrentLine),
@@ -90,72 +230,136 @@ fn get_input(so: &mut Stdout, instruction: &str) -> String {
                 code: KeyCode::Char(c),
                 modifiers: KeyModifiers::NONE,
             }) => {
-                res += std::str::from_utf8(&[c as u8]).unwrap();
+                res.push(c);
                 execute!(so, Print(c));
             }
             Event::Key(KeyEvent {
                 code: KeyCode::Char(c),
                 modifiers: KeyModifiers::SHIFT,
             }) => {
-                res += std::str::from_utf8(&[c as u8]).unwrap();
+                res.push(c);
                 execute!(so, Print(c));
             }
             _ => (),
         }
     }
 
-    res
+    Ok(res)
 }
 
-//Display the file selection menu when
-#[allow(unused_must_use)]
-fn file_selection(so: &mut Stdout) -> String {
-    let base_dir = "./selection_files/";
-    let mut paths = fs::read_dir(base_dir).unwrap();
-
-    //display menu
-    let mut i = 1;
-    queue!(so, Clear(ClearType::All), cursor::MoveTo(0, 0));
-    queue!(so, Print("Choose a file to load"));
-    let mut files: Vec<String> = vec![];
-    for p in &mut paths {
-        let mut file_name = p.unwrap().path().display().to_string();
-        file_name = file_name.replace(base_dir, "");
-        let s = format!("{} : {}", i, &file_name);
-        files.push(file_name);
-        queue!(so, cursor::MoveToNextLine(1), Print(s));
-
-        if i >= 9 {
+//lines entered one after the other until an empty one
+fn get_lines(so: &mut Stdout, instruction: &str) -> Result<String, Error> {
+    let mut lines = vec![];
+    loop {
+        let line = get_input(
+            so,
+            &format!(
+                "{}, line {} (an empty line to finish)",
+                instruction,
+                lines.len() + 1
+            ),
+        )?;
+        if line.is_empty() {
             break;
         }
-        i += 1;
+        lines.push(line);
     }
-    execute!(so);
-
-    let max = i;
-    let file_keys: Vec<usize> = (1..=max).collect();
+    Ok(lines.join("\n"))
+}
 
-    let mut res = String::new();
-    match event::read().unwrap() {
-        Event::Key(KeyEvent {
-            code: KeyCode::Char(c),
-            modifiers: KeyModifiers::NONE,
-        }) => {
-            for k in file_keys.iter() {
-                if c == std::char::from_digit((*k) as u32, 10).unwrap() {
-                    res = base_dir.to_string() + &files[*k - 1];
-                }
-            }
+//items joined in lines of at most width characters, the items too long being cut
+pub fn wrap(items: &[String], width: u16) -> Vec<String> {
+    let width = width.max(1) as usize;
+    let mut lines: Vec<String> = vec![];
+    let mut line = String::new();
+    for item in items {
+        let len = line.chars().count();
+        if len > 0 && len + 2 + item.chars().count() > width {
+            lines.push(line);
+            line = String::new();
+        }
+        if !line.is_empty() {
+            line.push_str("; ");
         }
-        _ => (),
+        line.push_str(item);
     }
-
-    res
+    lines.push(line);
+    lines.into_iter().map(|l| l.chars().take(width).collect()).collect()
 }
 
-//show infos about inputs and the game
+//prints the lines from the top of the terminal and returns how many there are
 #[allow(unused_must_use)]
-pub fn hud(so: &mut Stdout, c: usize, s: f32) {
-    queue!(so, Print("'q' to quit; 'x' to speed up; 'c' to slow down; arrows to move; 'z' to zoom; 'u' to unzoom"), cursor::MoveToNextLine(1));
-    queue!(so, Print(format!("Generation: {}; Speed: {}/s", c, s)));
+pub fn print_lines(so: &mut Stdout, lines: &[String]) -> u16 {
+    for (i, line) in lines.iter().enumerate() {
+        if i > 0 {
+            queue!(so, cursor::MoveToNextLine(1));
+        }
+        queue!(so, Print(line));
+    }
+    lines.len() as u16
+}
+
+//show infos about inputs and the game in lines of at most width characters, returns the number
+//of lines
+pub fn hud(
+    so: &mut Stdout,
+    game: &GameOfLife,
+    s: f32,
+    paused: bool,
+    keys: &Keys,
+    width: u16,
+) -> u16 {
+    let k = |a| keys.name(a);
+    let moves = [Action::Up, Action::Down, Action::Left, Action::Right];
+    let moves = if moves.iter().map(|a| k(*a)).eq(["up", "down", "left", "right"]) {
+        "arrows".to_string()
+    } else {
+        moves.iter().map(|a| format!("'{}'", k(*a))).collect::<Vec<String>>().join("/")
+    };
+    let mut help = vec![
+        format!("'{}' to quit", k(Action::Quit)),
+        format!("'{}' to {}", k(Action::Pause), if paused { "resume" } else { "pause" }),
+        format!("'{}'/'{}' to speed up/slow down", k(Action::SpeedUp), k(Action::SlowDown)),
+        format!("{} to move", moves),
+        format!("'{}'/'{}' to zoom/unzoom", k(Action::Zoom), k(Action::Unzoom)),
+    ];
+    if game.is_second_order() {
+        help.push(format!("'{}' to reverse time", k(Action::Reverse)));
+    }
+    if game.puzzle.is_none() {
+        help.push(format!(
+            "'{}'/'{}'/'{}'/'{}'/'{}' to rotate/flip/transpose/scale",
+            k(Action::Rotate),
+            k(Action::FlipHorizontal),
+            k(Action::FlipVertical),
+            k(Action::Transpose),
+            k(Action::Scale)
+        ));
+        help.push(format!("shift+{} to shift", moves));
+    }
+    if game.rule.lattice == Lattice::Square
+        && game.rule.elementary_number().is_none()
+        && game.puzzle.is_none()
+    {
+        help.push(format!("'{}' to edit", k(Action::Edit)));
+    }
+
+    let mut status = vec![
+        format!("Generation: {}", game.generation),
+        format!("Speed: {}/s", s),
+        format!("Rule: {}", game.description()),
+    ];
+    if paused {
+        status.push("Paused".to_string());
+    }
+    if let Some(p) = &game.puzzle {
+        status.push(format!("Goal: {}", p.description()));
+    }
+    match &game.metadata {
+        Ok(m) => status.extend(m.summary().map(|summary| format!("Pattern: {}", summary))),
+        Err(e) => status.push(format!("Pattern information: {}", e)),
+    }
+    let mut lines = wrap(&help, width);
+    lines.extend(wrap(&status, width));
+    print_lines(so, &lines)
 }
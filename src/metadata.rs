@@ -0,0 +1,129 @@
+use crate::error::Error;
+use crate::library;
+
+use std::path::Path;
+
+//what is known about a pattern, from the comments of its .rle file ("#N" name, "#O" author and
+//"#C" lines) and from a sidecar file with the same name ending with .meta, made of lines like
+//"period: 30". "#C" lines like "Tags: gun" are read like the lines of a sidecar file, the other
+//ones make the description
+#[derive(Clone, Default)]
+pub struct Metadata {
+    pub name: Option<String>,
+    pub author: Option<String>,
+    pub year: Option<u32>,
+    pub rule: Option<String>,
+    pub period: Option<u32>,
+    pub tags: Vec<String>,
+    pub description: Vec<String>,
+}
+
+impl Metadata {
+    pub fn read(path: &str) -> Result<Metadata, Error> {
+        let mut metadata = Metadata::default();
+        if path.ends_with(".rle") {
+            metadata.read_rle_comments(&library::read_to_string(path)?);
+        }
+
+        //the sidecar file takes precedence over the comments of the pattern
+        let sidecar = Path::new(path).with_extension("meta");
+        if library::is_file(&sidecar) {
+            for (line_index, line) in library::read_to_string(&sidecar)?.lines().enumerate() {
+                let line = line.trim();
+                if line.is_empty() || line.starts_with('#') {
+                    continue;
+                }
+                let error = |reason| meta_error(&sidecar, line_index, reason);
+                let (key, value) = line
+                    .split_once(':')
+                    .ok_or_else(|| error("expected '<key>: <value>'"))?;
+                if !metadata.set(key, value).map_err(error)? {
+                    return Err(error("unknown key"));
+                }
+            }
+        }
+        Ok(metadata)
+    }
+
+    //for patterns without a file, so without sidecar
+    pub fn from_rle(contents: &str) -> Metadata {
+        let mut metadata = Metadata::default();
+        metadata.read_rle_comments(contents);
+        metadata
+    }
+
+    fn read_rle_comments(&mut self, contents: &str) {
+        for line in contents.lines() {
+            let line = line.trim();
+            let (kind, text) = match line.get(..2) {
+                Some(k) if k.starts_with('#') => (k, line[2..].trim()),
+                _ => continue,
+            };
+            match kind {
+                "#N" => self.name = Some(text.to_string()),
+                "#O" => self.author = Some(text.to_string()),
+                "#C" | "#c" => {
+                    //comments that look like fields but can't be read are kept as text
+                    let field = match text.split_once(':') {
+                        Some((key, value)) => self.set(key, value).unwrap_or(false),
+                        None => false,
+                    };
+                    if !field && !text.is_empty() {
+                        self.description.push(text.to_string());
+                    }
+                }
+                _ => (),
+            }
+        }
+    }
+
+    //false when the key isn't known
+    fn set(&mut self, key: &str, value: &str) -> Result<bool, &'static str> {
+        let value = value.trim().to_string();
+        match key.trim().to_lowercase().as_str() {
+            "name" => self.name = Some(value),
+            "author" => self.author = Some(value),
+            "year" => self.year = Some(value.parse().map_err(|_| "invalid year")?),
+            "rule" => self.rule = Some(value),
+            "period" => self.period = Some(value.parse().map_err(|_| "invalid period")?),
+            "tags" => {
+                self.tags = value
+                    .split(',')
+                    .map(|t| t.trim().to_lowercase())
+                    .filter(|t| !t.is_empty())
+                    .collect()
+            }
+            "description" => self.description.push(value),
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    //the filter is looked for in the name and the tags
+    pub fn matches(&self, filter: &str) -> bool {
+        let filter = filter.to_lowercase();
+        self.name
+            .as_ref()
+            .is_some_and(|n| n.to_lowercase().contains(&filter))
+            || self.tags.iter().any(|t| t.contains(&filter))
+    }
+
+    //name, author, year and period on one line, None when the pattern has no name
+    pub fn summary(&self) -> Option<String> {
+        let mut res = self.name.clone()?;
+        if let Some(author) = &self.author {
+            res += &format!(" by {}", author);
+        }
+        if let Some(year) = self.year {
+            res += &format!(" ({})", year);
+        }
+        if let Some(period) = self.period {
+            res += &format!(", period {}", period);
+        }
+        Some(res)
+    }
+}
+
+fn meta_error(path: &Path, line_index: usize, reason: &str) -> Error {
+    Error::parse(line_index, 0, reason).in_file(&path.to_string_lossy())
+}
@@ -0,0 +1,306 @@
+use crate::camera::ViewRect;
+use crate::error::Error;
+use crate::library;
+use crate::menu::{print_lines, wrap};
+use crate::rule::{Lattice, Rule};
+use crate::tty::read_event;
+use crate::{check_gol_line, GameGrid, GameOfLife};
+
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
+use crossterm::terminal::{self, Clear, ClearType};
+use crossterm::{cursor, execute, queue};
+
+use std::collections::HashSet;
+use std::io::Stdout;
+
+//the four phases of a glider heading south east, in its 3x3 bounding box
+const GLIDER: [[(isize, isize); 5]; 4] = [
+    [(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)],
+    [(0, 0), (2, 0), (1, 1), (2, 1), (1, 2)],
+    [(2, 0), (0, 1), (2, 1), (1, 2), (2, 2)],
+    [(0, 0), (1, 1), (2, 1), (0, 2), (1, 2)],
+];
+
+enum Goal {
+    //no alive cell in the region
+    Empty {
+        x: isize,
+        y: isize,
+        width: isize,
+        height: isize,
+    },
+    //a lone glider moving by dx and dy every 4 generations
+    Glider { dx: isize, dy: isize },
+}
+
+//level read from a file: a starting pattern the player can change a few cells of, and a goal
+//to reach before a generation
+pub struct Puzzle {
+    pub name: String,
+    //number of cells the player can change before the start
+    budget: usize,
+    goal: Goal,
+    //last generation where the goal can be reached
+    by: isize,
+}
+impl Puzzle {
+    pub fn description(&self) -> String {
+        let goal = match self.goal {
+            Goal::Empty {
+                x,
+                y,
+                width,
+                height,
+            } => format!(
+                "empty the {}x{} region at ({}, {})",
+                width, height, x, y
+            ),
+            Goal::Glider { dx, dy } => format!(
+                "produce a glider heading {}{}",
+                if dy < 0 { "N" } else { "S" },
+                if dx < 0 { "W" } else { "E" }
+            ),
+        };
+        format!("{} by generation {}", goal, self.by)
+    }
+
+    fn is_reached(&self, g: &GameGrid) -> bool {
+        match self.goal {
+            Goal::Empty {
+                x,
+                y,
+                width,
+                height,
+            } => !g.alive_cells.coords.iter().any(|(i, j)| {
+                (x..x + width).contains(i) && (y..y + height).contains(j)
+            }),
+            Goal::Glider { dx, dy } => has_glider(g, dx, dy),
+        }
+    }
+}
+
+//a glider with no other alive cell around it
+fn has_glider(g: &GameGrid, dx: isize, dy: isize) -> bool {
+    let alive = &g.alive_cells.coords;
+    for phase in GLIDER.iter() {
+        //the glider heading south east mirrored to go in the right direction
+        let cells: Vec<(isize, isize)> = phase
+            .iter()
+            .map(|(x, y)| {
+                (
+                    if dx < 0 { 2 - x } else { *x },
+                    if dy < 0 { 2 - y } else { *y },
+                )
+            })
+            .collect();
+        for (x, y) in alive.iter() {
+            for (i, j) in cells.iter() {
+                let (ox, oy) = (x - i, y - j);
+                if !cells.iter().all(|(a, b)| alive.contains(&(ox + a, oy + b))) {
+                    continue;
+                }
+                let around = alive
+                    .iter()
+                    .filter(|(a, b)| (ox - 1..ox + 4).contains(a) && (oy - 1..oy + 4).contains(b))
+                    .count();
+                if around == cells.len() {
+                    return true;
+                }
+            }
+        }
+    }
+    false
+}
+
+//levels (.level files) are .gol patterns preceded by lines like "#budget 3" giving the name,
+//rule, budget and goal of the level, the goal being "empty <x> <y> <width> <height> by <n>" or
+//"glider <ne|nw|se|sw> by <n>"
+pub fn load(path: &str) -> Result<GameOfLife, Error> {
+    let contents = library::read_to_string(path)?;
+    let mut name = path.to_string();
+    let mut rule = Rule::conway();
+    let mut budget = 0;
+    let mut goal = None;
+    let mut rows = vec![];
+
+    for (line_index, line) in contents.lines().enumerate() {
+        //the rows of the pattern keep their leading dead cells
+        let header = match line.trim().strip_prefix('#') {
+            Some(h) => h,
+            None => {
+                check_gol_line(line, line_index).map_err(|e| e.in_file(path))?;
+                rows.push(line);
+                continue;
+            }
+        };
+        let (key, value) = header.split_once(' ').unwrap_or((header, ""));
+        let value = value.trim();
+        match key {
+            "name" => name = value.to_string(),
+            "rule" => rule = Rule::parse(value)?,
+            "budget" => {
+                budget = value
+                    .parse()
+                    .map_err(|_| level_error(path, line_index, "invalid budget"))?
+            }
+            "goal" => goal = Some(parse_goal(value).map_err(|e| level_error(path, line_index, e))?),
+            _ => return Err(level_error(path, line_index, "unknown header")),
+        }
+    }
+
+    let (goal, by) = goal.ok_or_else(|| level_error(path, 0, "the level has no goal"))?;
+    if rule.lattice != Lattice::Square || rule.elementary_number().is_some() {
+        return Err(level_error(path, 0, "levels only use two dimensional square grids"));
+    }
+
+    //set cell by cell so that the first character of the pattern is at (0, 0), like the goal.
+    //'b', 'c' and 'd' are the other colours of multi-colour rules, like in .gol files
+    let mut g = GameGrid::new();
+    for (y, row) in rows.iter().enumerate() {
+        for (x, c) in row.chars().enumerate() {
+            let (x, y) = (x as isize, y as isize);
+            match c {
+                'a'..='d' => g.set_cells(&[(x, y, c as u8 - b'a')]),
+                _ => g.set_cell(x, y, false),
+            }
+        }
+    }
+    let mut game = GameOfLife::new(g, rule);
+    game.puzzle = Some(Puzzle {
+        name,
+        budget,
+        goal,
+        by,
+    });
+    Ok(game)
+}
+
+fn parse_goal(s: &str) -> Result<(Goal, isize), &'static str> {
+    let words: Vec<&str> = s.split_whitespace().collect();
+    let (kind, by) = match words.as_slice() {
+        [kind @ .., "by", by] => (kind, by),
+        _ => return Err("the goal must end with 'by <generation>'"),
+    };
+    let by = by.parse().map_err(|_| "invalid generation")?;
+    let goal = match kind {
+        ["empty", region @ ..] if region.len() == 4 => {
+            let mut n = [0; 4];
+            for (i, v) in region.iter().enumerate() {
+                n[i] = v.parse().map_err(|_| "invalid region")?;
+            }
+            Goal::Empty {
+                x: n[0],
+                y: n[1],
+                width: n[2],
+                height: n[3],
+            }
+        }
+        ["glider", direction] => {
+            let (dx, dy) = match direction.to_lowercase().as_str() {
+                "ne" => (1, -1),
+                "nw" => (-1, -1),
+                "se" => (1, 1),
+                "sw" => (-1, 1),
+                _ => return Err("the glider direction must be ne, nw, se or sw"),
+            };
+            Goal::Glider { dx, dy }
+        }
+        _ => return Err("unknown goal"),
+    };
+    Ok((goal, by))
+}
+
+fn level_error(path: &str, line_index: usize, reason: &str) -> Error {
+    Error::parse(line_index, 0, reason).in_file(path)
+}
+
+impl GameOfLife {
+    //Some(true) once the goal is reached, Some(false) when it can't be anymore (or the pattern
+    //stopped changing), None outside of puzzles
+    pub fn puzzle_result(&self, over: bool) -> Option<bool> {
+        let puzzle = self.puzzle.as_ref()?;
+        if puzzle.is_reached(&self.game_grid) {
+            Some(true)
+        } else if over || self.generation >= puzzle.by {
+            Some(false)
+        } else {
+            None
+        }
+    }
+}
+
+//before the start the player changes up to `budget` cells of the pattern
+#[allow(unused_must_use)]
+pub fn edit(so: &mut Stdout, game: &mut GameOfLife) -> Result<(), Error> {
+    let (name, description, budget) = match &game.puzzle {
+        Some(p) => (p.name.clone(), p.description(), p.budget),
+        None => return Ok(()),
+    };
+    let size = terminal::size().map_err(Error::Terminal)?;
+    let mut camera = ViewRect::new(0, 0, size.0 as isize - 2, size.1 as isize - 4);
+    //changing a cell back gives it back to the budget
+    let mut edited = HashSet::new();
+    let (mut x, mut y) = (0, 0);
+
+    loop {
+        queue!(so, Clear(ClearType::All), cursor::MoveTo(0, 0));
+        let items = vec![
+            format!("Puzzle: {}", name),
+            format!("Goal: {}", description),
+            "arrows to move".to_string(),
+            "space to change a cell".to_string(),
+            "enter to start".to_string(),
+            format!("Cells left: {}/{}", budget - edited.len(), budget),
+            format!("Cursor: ({}, {})", x, y),
+        ];
+        let size = terminal::size().map_err(Error::Terminal)?;
+        let lines = print_lines(so, &wrap(&items, size.0));
+        camera.fit(size.0, size.1, lines);
+        x = x.min(camera.x + camera.x_len - 1);
+        y = y.min(camera.y + camera.y_len - 1);
+        game.show_in_camera(so, &camera);
+        //the terminal's cursor is put on the cell under the cursor, below the lines of text and
+        //the border
+        execute!(
+            so,
+            cursor::MoveTo((x - camera.x + 1) as u16, (y - camera.y + lines as isize + 1) as u16)
+        );
+
+        match read_event()? {
+            Event::Key(KeyEvent {
+                code: KeyCode::Up,
+                modifiers: KeyModifiers::NONE,
+            }) => y = (y - 1).max(camera.y),
+            Event::Key(KeyEvent {
+                code: KeyCode::Down,
+                modifiers: KeyModifiers::NONE,
+            }) => y = (y + 1).min(camera.y + camera.y_len - 1),
+            Event::Key(KeyEvent {
+                code: KeyCode::Left,
+                modifiers: KeyModifiers::NONE,
+            }) => x = (x - 1).max(camera.x),
+            Event::Key(KeyEvent {
+                code: KeyCode::Right,
+                modifiers: KeyModifiers::NONE,
+            }) => x = (x + 1).min(camera.x + camera.x_len - 1),
+            Event::Key(KeyEvent {
+                code: KeyCode::Char(' '),
+                modifiers: KeyModifiers::NONE,
+            }) if edited.contains(&(x, y)) || edited.len() < budget => {
+                if !edited.remove(&(x, y)) {
+                    edited.insert((x, y));
+                }
+                let alive = game.game_grid.is_alive(x, y);
+                game.game_grid.set_cell(x, y, !alive);
+            }
+            Event::Key(KeyEvent {
+                code: KeyCode::Enter,
+                modifiers: KeyModifiers::NONE,
+            }) => break,
+            _ => (),
+        }
+    }
+
+    game.game_grid.update_edges(game.rule.radius());
+    Ok(())
+}
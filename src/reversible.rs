@@ -0,0 +1,58 @@
+use crate::error::Error;
+use crate::GameOfLife;
+
+use std::mem;
+
+//second-order automata compute the next generation from the rule applied to the current one
+//xor the previous one, which makes them reversible: the previous generation is the rule
+//applied to the current one xor the next one
+impl GameOfLife {
+    //the generation before the first one is empty
+    pub fn set_second_order(&mut self, second_order: bool) -> Result<(), Error> {
+        if !second_order {
+            self.previous = None;
+            return Ok(());
+        }
+        if self.rule.elementary_number().is_some() || self.rule.margolus_table(0).is_some() {
+            return Err(Error::invalid(
+                "the second-order mode only works with two dimensional cell rules",
+            ));
+        }
+        let mut empty = self.game_grid.clone();
+        empty.clear();
+        self.previous = Some(empty);
+        Ok(())
+    }
+
+    pub fn is_second_order(&self) -> bool {
+        self.previous.is_some()
+    }
+
+    //swapping the current and previous generations reverses time and goes back one generation
+    pub fn reverse(&mut self) {
+        if let Some(previous) = self.previous.as_mut() {
+            mem::swap(previous, &mut self.game_grid);
+            self.backwards = !self.backwards;
+            self.generation += if self.backwards { -1 } else { 1 };
+        }
+    }
+
+    pub fn next_second_order(&mut self) -> bool {
+        let previous = self.previous.take().unwrap();
+        let current = self.game_grid.clone();
+
+        self.game_grid.next(&self.rule, &mut self.chance);
+        for (x, y) in previous.alive_cells.coords.iter() {
+            let alive = self.game_grid.is_alive(*x, *y);
+            self.game_grid.set_cell(*x, *y, !alive);
+        }
+        self.game_grid.update_edges(self.rule.radius());
+
+        //the generation after the next one also depends on the previous one, so the pattern
+        //only stays the same when the three generations are identical
+        let changed = current.alive_cells.coords != self.game_grid.alive_cells.coords
+            || current.alive_cells.coords != previous.alive_cells.coords;
+        self.previous = Some(current);
+        changed
+    }
+}
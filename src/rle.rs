@@ -0,0 +1,177 @@
+use crate::error::Error;
+use crate::rule::Rule;
+use crate::transform;
+
+//pattern read from a run length encoded file, converted to the text used by .gol files
+pub struct Rle {
+    pub text: String,
+    pub rule: Option<String>,
+}
+
+pub fn parse(contents: &str) -> Result<Rle, Error> {
+    let mut rule = None;
+    let mut width = 0;
+    let mut rows: Vec<String> = vec![String::new()];
+    let mut count = String::new();
+    let mut header_read = false;
+    let mut longest = 0;
+
+    'lines: for (line_index, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if !header_read && line.starts_with('x') {
+            header_read = true;
+            for part in line.split(',') {
+                let mut kv = part.splitn(2, '=');
+                if let (Some("x"), Some(v)) = (kv.next().map(str::trim), kv.next()) {
+                    width = v
+                        .trim()
+                        .parse()
+                        .map_err(|_| rle_error(line_index, 1, "invalid width"))?;
+                    transform::check_size(width, 1)
+                        .map_err(|_| rle_error(line_index, 1, "the pattern is too large"))?;
+                }
+            }
+            //the rule is always last and can itself contain commas
+            if let Some(i) = line.find("rule") {
+                if let Some((_, v)) = line[i..].split_once('=') {
+                    rule = Some(v.trim().to_string());
+                }
+            }
+            continue;
+        }
+
+        for (column, c) in line.chars().enumerate() {
+            match c {
+                '0'..='9' => {
+                    count.push(c);
+                    continue;
+                }
+                _ if c.is_whitespace() => continue,
+                '!' => break 'lines,
+                _ => (),
+            }
+
+            let n = if count.is_empty() {
+                1
+            } else {
+                count
+                    .parse()
+                    .ok()
+                    .filter(|n| *n <= transform::MAX_GRID_CELLS)
+                    .ok_or_else(|| rle_error(line_index, column + 1, "run too long"))?
+            };
+            count.clear();
+            match c {
+                'b' | '.' => rows.last_mut().unwrap().push_str(&"-".repeat(n)),
+                //states of multi-colour rules are colours, written with the next letters
+                'o' | 'A' | 'E'..='X' => rows.last_mut().unwrap().push_str(&"a".repeat(n)),
+                'B'..='D' => {
+                    let colour = (c as u8 - b'A' + b'a') as char;
+                    rows.last_mut()
+                        .unwrap()
+                        .push_str(&colour.to_string().repeat(n))
+                }
+                '$' => {
+                    for _ in 0..n {
+                        rows.push(String::new());
+                    }
+                }
+                _ => {
+                    return Err(Error::UnknownCharacter {
+                        file: None,
+                        line: line_index + 1,
+                        column: column + 1,
+                        character: c,
+                    })
+                }
+            }
+            //the rows are padded to the same width at the end
+            longest = longest.max(rows.last().unwrap().len());
+            transform::check_size(longest.max(width).max(1), rows.len())
+                .map_err(|_| rle_error(line_index, column + 1, "the pattern is too large"))?;
+        }
+    }
+
+    let width = longest.max(width);
+    let text = rows
+        .iter()
+        .map(|r| format!("{}{}", r, "-".repeat(width - r.len())))
+        .collect::<Vec<String>>()
+        .join("\n");
+
+    Ok(Rle { text, rule })
+}
+
+//for patterns without a file name: they are run length encoded when they start with a header
+//("x = 3, y = 3") or end with '!', otherwise they are read like .gol files
+pub fn is_rle(contents: &str) -> bool {
+    let mut lines = contents
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#'));
+    let header = lines
+        .clone()
+        .next()
+        .and_then(|l| l.strip_prefix('x'))
+        .is_some_and(|rest| rest.trim_start().starts_with('='));
+    header || lines.any(|l| l.ends_with('!'))
+}
+
+//run length encoding of the alive cells (x, y, colour) of a width x height rectangle starting at
+//(0, 0), colours are written with the states A to D when the rule has several of them
+pub fn encode(cells: &[(isize, isize, u8)], width: usize, height: usize, rule: &Rule) -> String {
+    let mut grid = vec![vec![None; width]; height];
+    for (x, y, colour) in cells.iter() {
+        grid[*y as usize][*x as usize] = Some(*colour);
+    }
+
+    //runs of the same state, ending with the number of rows to skip
+    let mut runs: Vec<(usize, char)> = vec![];
+    let mut push = |n: usize, c: char| match runs.last_mut() {
+        Some((m, last)) if *last == c => *m += n,
+        _ => runs.push((n, c)),
+    };
+    for (i, row) in grid.iter().enumerate() {
+        //trailing dead cells are not written
+        let len = row.iter().rposition(|c| c.is_some()).map_or(0, |p| p + 1);
+        for c in row[..len].iter() {
+            let state = match c {
+                None => 'b',
+                Some(_) if rule.colours == 1 => 'o',
+                Some(colour) => (b'A' + colour) as char,
+            };
+            push(1, state);
+        }
+        if i + 1 < height {
+            push(1, '$');
+        }
+    }
+    //the rows after the last alive cell are not written
+    while let Some((_, '$')) = runs.last() {
+        runs.pop();
+    }
+
+    let mut res = format!("x = {}, y = {}, rule = {}\n", width, height, rule.name);
+    let mut line = String::new();
+    for (n, c) in runs.iter() {
+        let run = match n {
+            1 => c.to_string(),
+            _ => format!("{}{}", n, c),
+        };
+        //lines of RLE files are at most 70 characters long
+        if line.len() + run.len() > 70 {
+            res += &line;
+            res += "\n";
+            line.clear();
+        }
+        line += &run;
+    }
+    res + &line + "!\n"
+}
+
+fn rle_error(line_index: usize, column: usize, reason: &str) -> Error {
+    Error::parse(line_index, column, reason)
+}
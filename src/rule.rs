@@ -0,0 +1,613 @@
+use crate::error::Error;
+
+use std::ops::RangeInclusive;
+
+//offsets of the neighbours in the order used for the neighbourhood bitmask
+//bit 0 is the top left neighbour and bit 7 the bottom right one
+pub const MOORE: [(isize, isize); 8] = [
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (-1, 0),
+    (1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+];
+
+//the three cells of the previous generation for one dimensional automata
+const ELEMENTARY: [(isize, isize); 3] = [(-1, -1), (0, -1), (1, -1)];
+
+const VON_NEUMANN: [(isize, isize); 4] = [(0, -1), (-1, 0), (1, 0), (0, 1)];
+
+//hexagonal grids are stored with every odd row shifted right by half a cell
+const HEXAGONAL_EVEN_ROW: [(isize, isize); 6] =
+    [(-1, -1), (0, -1), (-1, 0), (1, 0), (-1, 1), (0, 1)];
+const HEXAGONAL_ODD_ROW: [(isize, isize); 6] = [(0, -1), (1, -1), (-1, 0), (1, 0), (0, 1), (1, 1)];
+
+//triangles point up when x + y is even, every triangle sharing a corner is a neighbour
+const TRIANGULAR_UP: [(isize, isize); 12] = [
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (-2, 0),
+    (-1, 0),
+    (1, 0),
+    (2, 0),
+    (-2, 1),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+    (2, 1),
+];
+const TRIANGULAR_DOWN: [(isize, isize); 12] = [
+    (-2, -1),
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (2, -1),
+    (-2, 0),
+    (-1, 0),
+    (1, 0),
+    (2, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+];
+
+//Hensel letters for 1 to 4 neighbours and one configuration for each of them
+//(configurations use the 3x3 layout with the centre as bit 4, as found on LifeWiki and Golly)
+//5 to 7 neighbours use the complement of the configuration for 8 - n
+const HENSEL: [(&str, &[u16]); 4] = [
+    ("ce", &[1, 2]),
+    ("ceaikn", &[5, 10, 3, 40, 33, 68]),
+    ("ceaiknjqry", &[69, 42, 11, 7, 98, 13, 14, 70, 41, 97]),
+    (
+        "ceaiknjqrytwz",
+        &[325, 170, 15, 45, 99, 71, 106, 102, 43, 101, 105, 78, 108],
+    ),
+];
+
+const MAX_RADIUS: isize = 50;
+
+//suffixes giving alive cells a colour, births take the colour of the majority of their parents
+const COLOUR_SUFFIXES: [(&str, u8); 2] = [("IMMIGRATION", 2), ("QUADLIFE", 4)];
+
+//Margolus rules known by name, in MCell notation
+const MARGOLUS_RULES: [(&str, &str); 3] = [
+    ("Critters", "MS,D15;14;13;3;11;5;6;1;7;9;10;2;12;4;8;0"),
+    ("BBM", "MS,D0;8;4;3;2;5;9;7;1;6;10;11;12;13;14;15"),
+    ("Tron", "MS,D15;1;2;3;4;5;6;7;8;9;10;11;12;13;14;0"),
+];
+
+#[derive(Clone)]
+pub struct Rule {
+    pub name: String,
+    pub lattice: Lattice,
+    //number of colours alive cells can have, 1 for rules without colours
+    pub colours: u8,
+    //offsets of the cells counted as neighbours on the square lattice
+    neighbourhood: Vec<(isize, isize)>,
+    radius: isize,
+    kind: RuleKind,
+}
+
+#[derive(Clone)]
+enum RuleKind {
+    //indexed by the neighbourhood bitmask
+    Isotropic {
+        birth: Vec<bool>,
+        survival: Vec<bool>,
+    },
+    //ranges of alive cells in the neighbourhood
+    LargerThanLife {
+        birth: RangeInclusive<u32>,
+        survival: RangeInclusive<u32>,
+        middle: bool,
+    },
+    //Wolfram's number of a one dimensional rule
+    Elementary(u8),
+    //new state of each 2x2 block, a block's state has 1 for the top left cell, 2 for the top
+    //right one, 4 for the bottom left one and 8 for the bottom right one
+    Margolus([u8; 16]),
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Lattice {
+    Square,
+    Hexagonal,
+    Triangular,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Shape {
+    Moore,
+    VonNeumann,
+    Circular,
+}
+impl Shape {
+    fn contains(&self, x: isize, y: isize, radius: isize) -> bool {
+        match self {
+            Shape::Moore => true,
+            Shape::VonNeumann => x.abs() + y.abs() <= radius,
+            Shape::Circular => x * x + y * y <= radius * radius + radius,
+        }
+    }
+}
+
+impl Rule {
+    pub fn conway() -> Rule {
+        Rule::parse("B3/S23").unwrap()
+    }
+
+    //accepts B/S notation ("B3/S23", "B2-a/S12", "B2ac3i/S23-k"), the older S/B one ("23/3")
+    //, Larger than Life ("R5,C0,M1,S34..58,B34..45,NM"), one dimensional rules ("W30") and
+    //Margolus rules ("MS,D0;8;4;3;2;5;9;7;1;6;10;11;12;13;14;15" or a name like "Critters")
+    //B/S rules ending with H use the hexagonal grid, with T the triangular one and with V the
+    //von Neumann neighbourhood, all three only take neighbour counts (no Hensel letters)
+    //"Immigration" and "QuadLife" are Conway's rule with 2 and 4 colours, they can also follow
+    //another two dimensional rule ("B36/S23QuadLife")
+    pub fn parse(s: &str) -> Result<Rule, Error> {
+        let name = s.trim().to_string();
+        for (suffix, colours) in COLOUR_SUFFIXES.iter() {
+            let split = name.len().saturating_sub(suffix.len());
+            if name.is_char_boundary(split) && name[split..].eq_ignore_ascii_case(suffix) {
+                let base = &name[..split];
+                let mut rule = match base {
+                    "" => Rule::conway(),
+                    _ => Rule::parse(base)?,
+                };
+                if rule.elementary_number().is_some() || rule.margolus_table(0).is_some() {
+                    return Err(invalid_rule(&name, "colours need a two dimensional cell rule"));
+                }
+                rule.colours = *colours;
+                rule.name = name;
+                return Ok(rule);
+            }
+        }
+
+        if name.starts_with(['R', 'r']) {
+            return parse_larger_than_life(&name);
+        }
+        if let Some((n, r)) = MARGOLUS_RULES
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(&name))
+        {
+            let mut rule = parse_margolus(r)?;
+            rule.name = n.to_string();
+            return Ok(rule);
+        }
+        if name.to_uppercase().starts_with("MS,D") {
+            return parse_margolus(&name);
+        }
+        if name.starts_with(['W', 'w']) {
+            return match name[1..].parse() {
+                Ok(n) => Ok(Rule::elementary(n)),
+                Err(_) => Err(invalid_rule(&name, "expected a number between 0 and 255")),
+            };
+        }
+
+        let (conditions, lattice, neighbourhood) = match name.chars().last() {
+            Some('H') => (&name[..name.len() - 1], Lattice::Hexagonal, vec![]),
+            Some('T') => (&name[..name.len() - 1], Lattice::Triangular, vec![]),
+            Some('V') => (
+                &name[..name.len() - 1],
+                Lattice::Square,
+                VON_NEUMANN.to_vec(),
+            ),
+            _ => (&name[..], Lattice::Square, MOORE.to_vec()),
+        };
+        let size = match lattice {
+            Lattice::Square => neighbourhood.len(),
+            Lattice::Hexagonal => HEXAGONAL_EVEN_ROW.len(),
+            Lattice::Triangular => TRIANGULAR_UP.len(),
+        };
+        let hensel = size == MOORE.len();
+
+        let parts: Vec<&str> = conditions.split('/').collect();
+        if parts.len() != 2 {
+            return Err(invalid_rule(&name, "expected two parts separated by '/'"));
+        }
+
+        let (birth, survival) = match (first_upper(parts[0]), first_upper(parts[1])) {
+            (Some('B'), Some('S')) => (&parts[0][1..], &parts[1][1..]),
+            (Some('S'), Some('B')) => (&parts[1][1..], &parts[0][1..]),
+            _ => (parts[1], parts[0]),
+        };
+
+        let birth = parse_conditions(birth, size, hensel).map_err(|e| invalid_rule(&name, &e))?;
+        let survival =
+            parse_conditions(survival, size, hensel).map_err(|e| invalid_rule(&name, &e))?;
+        //the whole infinite background would come alive
+        if birth[0] {
+            return Err(invalid_rule(&name, "rules with B0 are not supported"));
+        }
+
+        Ok(Rule {
+            name: name.to_uppercase(),
+            lattice,
+            colours: 1,
+            neighbourhood,
+            radius: if lattice == Lattice::Triangular { 2 } else { 1 },
+            kind: RuleKind::Isotropic { birth, survival },
+        })
+    }
+
+    pub fn elementary(n: u8) -> Rule {
+        Rule {
+            name: format!("W{}", n),
+            lattice: Lattice::Square,
+            colours: 1,
+            neighbourhood: ELEMENTARY.to_vec(),
+            radius: 1,
+            kind: RuleKind::Elementary(n),
+        }
+    }
+
+    //Wolfram's number if this is a one dimensional rule
+    pub fn elementary_number(&self) -> Option<u8> {
+        match self.kind {
+            RuleKind::Elementary(n) => Some(n),
+            _ => None,
+        }
+    }
+
+    //table to apply to the blocks at this generation if this is a Margolus rule
+    //when empty blocks become full, every other generation is stored inverted so that the
+    //background stays empty (like Golly does with B0 rules)
+    pub fn margolus_table(&self, generation: isize) -> Option<[u8; 16]> {
+        let table = match self.kind {
+            RuleKind::Margolus(t) => t,
+            _ => return None,
+        };
+        if table[0] == 0 {
+            return Some(table);
+        }
+
+        let mut res = [0; 16];
+        for (i, r) in res.iter_mut().enumerate() {
+            *r = if generation.rem_euclid(2) == 0 {
+                15 ^ table[i]
+            } else {
+                table[15 ^ i]
+            };
+        }
+        Some(res)
+    }
+
+    //offsets of the neighbours of the cell at x, y
+    pub fn neighbourhood(&self, x: isize, y: isize) -> &[(isize, isize)] {
+        match self.lattice {
+            Lattice::Square => &self.neighbourhood,
+            Lattice::Hexagonal if y.rem_euclid(2) == 0 => &HEXAGONAL_EVEN_ROW,
+            Lattice::Hexagonal => &HEXAGONAL_ODD_ROW,
+            Lattice::Triangular if (x + y).rem_euclid(2) == 0 => &TRIANGULAR_UP,
+            Lattice::Triangular => &TRIANGULAR_DOWN,
+        }
+    }
+
+    //how far from an alive cell a birth can happen
+    pub fn radius(&self) -> isize {
+        self.radius
+    }
+
+    //if true neighbours are given to next_state as a bitmask (bit i for the i-th offset of
+    //neighbourhood(x, y)), otherwise as the count of alive neighbours
+    pub fn uses_bitmask(&self) -> bool {
+        !matches!(self.kind, RuleKind::LargerThanLife { .. })
+    }
+
+    //colour of a cell born from parents with these colours: the colour of the majority of them,
+    //or when there is no majority the lowest colour the parents don't have (QuadLife's rule)
+    pub fn birth_colour(&self, parents: &[u8]) -> u8 {
+        let n = self.colours.max(1) as usize;
+        let mut counts = vec![0; n];
+        for p in parents.iter() {
+            counts[*p as usize % n] += 1;
+        }
+        let max = *counts.iter().max().unwrap();
+        let majority: Vec<usize> = (0..counts.len()).filter(|c| counts[*c] == max).collect();
+        if majority.len() == 1 {
+            return majority[0] as u8;
+        }
+        match counts.iter().position(|n| *n == 0) {
+            Some(c) => c as u8,
+            None => majority[0] as u8,
+        }
+    }
+
+    pub fn next_state(&self, alive: bool, neighbours: u32) -> bool {
+        match &self.kind {
+            RuleKind::Isotropic { birth, survival } => {
+                if alive {
+                    survival[neighbours as usize]
+                } else {
+                    birth[neighbours as usize]
+                }
+            }
+            RuleKind::LargerThanLife {
+                birth,
+                survival,
+                middle,
+            } => {
+                if alive {
+                    let count = if *middle { neighbours + 1 } else { neighbours };
+                    survival.contains(&count)
+                } else {
+                    birth.contains(&neighbours)
+                }
+            }
+            //blocks are computed by GameGrid::next_margolus
+            RuleKind::Margolus(_) => alive,
+            RuleKind::Elementary(n) => {
+                //the left cell is the most significant bit of Wolfram's index
+                let index = ((neighbours & 1) << 2) | (neighbours & 2) | ((neighbours >> 2) & 1);
+                (n >> index) & 1 == 1
+            }
+        }
+    }
+}
+
+//Rr,Cc,Mm,Smin..max,Bmin..max,Nn as used by Golly
+fn parse_larger_than_life(name: &str) -> Result<Rule, Error> {
+    let mut radius = None;
+    let mut middle = false;
+    let mut birth = None;
+    let mut survival = None;
+    let mut shape = Shape::Moore;
+
+    for part in name.split(',') {
+        let part = part.trim().to_uppercase();
+        let (key, value) = part.split_at(1.min(part.len()));
+        match key {
+            "R" => {
+                let r: isize = value
+                    .parse()
+                    .map_err(|_| invalid_rule(name, "invalid radius"))?;
+                if !(1..=MAX_RADIUS).contains(&r) {
+                    return Err(invalid_rule(
+                        name,
+                        &format!("the radius must be between 1 and {}", MAX_RADIUS),
+                    ));
+                }
+                radius = Some(r);
+            }
+            "C" => {
+                if value != "0" && value != "2" {
+                    return Err(invalid_rule(
+                        name,
+                        "only rules with two states are supported",
+                    ));
+                }
+            }
+            "M" => match value {
+                "0" => middle = false,
+                "1" => middle = true,
+                _ => return Err(invalid_rule(name, "M must be 0 or 1")),
+            },
+            "S" => {
+                survival =
+                    Some(parse_range(value).ok_or_else(|| invalid_rule(name, "invalid S range"))?)
+            }
+            "B" => {
+                birth =
+                    Some(parse_range(value).ok_or_else(|| invalid_rule(name, "invalid B range"))?)
+            }
+            "N" => {
+                shape = match value {
+                    "M" => Shape::Moore,
+                    "N" => Shape::VonNeumann,
+                    "C" => Shape::Circular,
+                    _ => return Err(invalid_rule(name, "the neighbourhood must be NM, NN or NC")),
+                }
+            }
+            _ => return Err(invalid_rule(name, &format!("unexpected '{}'", part))),
+        }
+    }
+
+    let radius = radius.ok_or_else(|| invalid_rule(name, "missing radius"))?;
+    let birth = birth.ok_or_else(|| invalid_rule(name, "missing B range"))?;
+    let survival = survival.ok_or_else(|| invalid_rule(name, "missing S range"))?;
+    if birth.contains(&0) {
+        return Err(invalid_rule(
+            name,
+            "births without neighbours are not supported",
+        ));
+    }
+
+    let mut neighbourhood = vec![];
+    for y in -radius..=radius {
+        for x in -radius..=radius {
+            if (x != 0 || y != 0) && shape.contains(x, y, radius) {
+                neighbourhood.push((x, y));
+            }
+        }
+    }
+
+    Ok(Rule {
+        name: name.to_uppercase(),
+        lattice: Lattice::Square,
+        colours: 1,
+        neighbourhood,
+        radius,
+        kind: RuleKind::LargerThanLife {
+            birth,
+            survival,
+            middle,
+        },
+    })
+}
+
+//MS,D followed by the new state of the 16 blocks, as used by MCell
+fn parse_margolus(name: &str) -> Result<Rule, Error> {
+    let mut table = [0; 16];
+    let states: Vec<&str> = name[4..].split(';').collect();
+    if states.len() != 16 {
+        return Err(invalid_rule(name, "expected 16 states separated by ';'"));
+    }
+    for (i, s) in states.iter().enumerate() {
+        table[i] = match s.trim().parse() {
+            Ok(n) if n < 16 => n,
+            _ => return Err(invalid_rule(name, &format!("invalid state '{}'", s))),
+        };
+    }
+    for i in 0..16 {
+        if !table.contains(&i) {
+            return Err(invalid_rule(
+                name,
+                "the states must be a permutation of 0 to 15",
+            ));
+        }
+    }
+    //the background would not come back to empty every other generation
+    if table[0] != 0 && (table[0] != 15 || table[15] != 0) {
+        return Err(invalid_rule(
+            name,
+            "empty blocks must stay empty or become full and full blocks empty",
+        ));
+    }
+
+    Ok(Rule {
+        name: name.to_uppercase(),
+        lattice: Lattice::Square,
+        colours: 1,
+        neighbourhood: vec![],
+        radius: 1,
+        kind: RuleKind::Margolus(table),
+    })
+}
+
+//"34..58" or a single number
+fn parse_range(s: &str) -> Option<RangeInclusive<u32>> {
+    match s.split_once("..") {
+        Some((min, max)) => Some(min.parse().ok()?..=max.parse().ok()?),
+        None => {
+            let n = s.parse().ok()?;
+            Some(n..=n)
+        }
+    }
+}
+
+fn first_upper(s: &str) -> Option<char> {
+    s.chars().next().map(|c| c.to_ascii_uppercase())
+}
+
+fn invalid_rule(rule: &str, reason: &str) -> Error {
+    Error::Invalid(format!("invalid rule '{}': {}", rule, reason))
+}
+
+//parse the part after B or S, giving for each neighbourhood bitmask if the condition is met
+//size is the number of neighbours, Hensel letters are only allowed if hensel is true
+fn parse_conditions(s: &str, size: usize, hensel: bool) -> Result<Vec<bool>, String> {
+    let mut table = vec![false; 1 << size];
+    let chars: Vec<char> = s.to_lowercase().chars().collect();
+
+    let mut i = 0;
+    while i < chars.len() {
+        let n = match chars[i].to_digit(10) {
+            Some(n) if n as usize <= size => n as usize,
+            Some(n) => return Err(format!("there are only {} neighbours, not {}", size, n)),
+            None => return Err(format!("unexpected character '{}'", chars[i])),
+        };
+        i += 1;
+
+        let negate = i < chars.len() && chars[i] == '-';
+        if negate {
+            i += 1;
+        }
+        let mut letters = String::new();
+        while i < chars.len() && chars[i].is_ascii_alphabetic() {
+            letters.push(chars[i]);
+            i += 1;
+        }
+        if negate && letters.is_empty() {
+            return Err(format!("'-' after {} must be followed by letters", n));
+        }
+        if !hensel && !letters.is_empty() {
+            return Err("letters can only be used with the Moore neighbourhood".to_string());
+        }
+
+        let available = hensel_letters(n);
+        for l in letters.chars() {
+            if !available.contains(l) {
+                return Err(format!(
+                    "'{}' is not a valid letter for {} neighbours",
+                    l, n
+                ));
+            }
+        }
+
+        for (mask, value) in table.iter_mut().enumerate() {
+            if mask.count_ones() as usize != n {
+                continue;
+            }
+            if letters.is_empty() {
+                *value = true;
+            } else {
+                let in_letters = letters
+                    .chars()
+                    .any(|l| hensel_class(n, l).contains(&(mask as u8)));
+                *value |= in_letters != negate;
+            }
+        }
+    }
+
+    Ok(table)
+}
+
+fn hensel_letters(n: usize) -> &'static str {
+    match n {
+        1..=4 => HENSEL[n - 1].0,
+        5..=7 => HENSEL[7 - n].0,
+        _ => "",
+    }
+}
+
+//every neighbourhood bitmask described by a count and a letter
+fn hensel_class(n: usize, letter: char) -> Vec<u8> {
+    let (letters, configurations) = if n <= 4 { HENSEL[n - 1] } else { HENSEL[7 - n] };
+    let index = letters.find(letter).unwrap();
+    let mut mask = from_layout(configurations[index]);
+    if n > 4 {
+        mask = !mask;
+    }
+
+    let mut class = vec![];
+    for reflect in [false, true].iter() {
+        for rotations in 0..4 {
+            let m = transform(mask, *reflect, rotations);
+            if !class.contains(&m) {
+                class.push(m);
+            }
+        }
+    }
+    class
+}
+
+//convert a 3x3 layout (centre as bit 4) to a neighbourhood bitmask
+fn from_layout(layout: u16) -> u8 {
+    let low = layout & 0b1111;
+    let high = (layout >> 5) & 0b1111;
+    (low | (high << 4)) as u8
+}
+
+fn transform(mask: u8, reflect: bool, rotations: u8) -> u8 {
+    let mut res = 0;
+    for (bit, offset) in MOORE.iter().enumerate() {
+        if mask & (1 << bit) == 0 {
+            continue;
+        }
+        let (mut x, mut y) = *offset;
+        if reflect {
+            x = -x;
+        }
+        for _ in 0..rotations {
+            let t = x;
+            x = -y;
+            y = t;
+        }
+        let target = MOORE.iter().position(|o| *o == (x, y)).unwrap();
+        res |= 1 << target;
+    }
+    res
+}
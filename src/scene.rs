@@ -0,0 +1,92 @@
+use crate::error::Error;
+use crate::library;
+use crate::rule::Rule;
+use crate::transform::{self, Cell, Transform};
+use crate::{GameGrid, GameOfLife};
+
+use std::convert::TryFrom;
+use std::path::Path;
+
+//scenes put several patterns in one grid, every line of a .scene file being
+//"<path> <x> <y> [transformations]" where the path is relative to the scene, (x, y) is where the
+//top left corner of the pattern goes and the transformations are written like the --transform
+//option. "#rule <rule>" sets the rule, otherwise the first rule given by a pattern is used
+pub fn load(path: &str) -> Result<GameOfLife, Error> {
+    let contents = library::read_to_string(path)?;
+    let dir = Path::new(path).parent().unwrap_or_else(|| Path::new(""));
+    let mut rule = None;
+    let mut pattern_rule = None;
+    let mut g = GameGrid::new();
+    //bounding box of the grid, which starts with the cell (0, 0)
+    let mut area = (0, 0, 0, 0);
+
+    for (line_index, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        //the other lines starting with '#' are comments
+        if let Some(header) = line.strip_prefix('#') {
+            let (key, value) = header.split_once(' ').unwrap_or((header, ""));
+            if key == "rule" {
+                rule = Some(Rule::parse(value.trim())?);
+            }
+            continue;
+        }
+        if line.is_empty() {
+            continue;
+        }
+
+        let words: Vec<&str> = line.split_whitespace().collect();
+        if words.len() < 3 {
+            return Err(scene_error(path, line_index, "expected '<path> <x> <y>'"));
+        }
+        let x: isize = words[1]
+            .parse()
+            .map_err(|_| scene_error(path, line_index, "invalid x"))?;
+        let y: isize = words[2]
+            .parse()
+            .map_err(|_| scene_error(path, line_index, "invalid y"))?;
+
+        let pattern_path = dir.join(words[0]);
+        let (mut pattern, r) = GameGrid::read(&pattern_path.to_string_lossy())?;
+        if pattern_rule.is_none() {
+            pattern_rule = r;
+        }
+
+        let mut cells = pattern.alive_cells_with_colour();
+        for w in words[3..].iter() {
+            let t: Transform = w.parse().map_err(|_| {
+                scene_error(path, line_index, &format!("invalid transformation '{}'", w))
+            })?;
+            if let Some(b) = transform::bounds(&cells) {
+                transform::check_transform(t, b)
+                    .map_err(|e| scene_error(path, line_index, &e.to_string()))?;
+                cells = t.apply(&cells, b);
+            }
+        }
+        if let Some((min_x, min_y, max_x, max_y)) = transform::bounds(&cells) {
+            //the grid goes from the patterns already placed to this one
+            let too_large = || scene_error(path, line_index, "the pattern would be too large");
+            let right = x.checked_add(max_x - min_x).ok_or_else(too_large)?.max(area.2);
+            let bottom = y.checked_add(max_y - min_y).ok_or_else(too_large)?.max(area.3);
+            let (left, top) = (area.0.min(x), area.1.min(y));
+            let span = |a: isize, b: isize| usize::try_from(b as i128 - a as i128 + 1);
+            match (span(left, right), span(top, bottom)) {
+                (Ok(w), Ok(h)) if transform::check_size(w, h).is_ok() => (),
+                _ => return Err(too_large()),
+            }
+            area = (left, top, right, bottom);
+
+            let placed: Vec<Cell> = cells
+                .iter()
+                .map(|(i, j, c)| (i - min_x + x, j - min_y + y, *c))
+                .collect();
+            g.set_cells(&placed);
+        }
+    }
+
+    let rule = rule.or(pattern_rule).unwrap_or_else(Rule::conway);
+    Ok(GameOfLife::new(g, rule))
+}
+
+fn scene_error(path: &str, line_index: usize, reason: &str) -> Error {
+    Error::parse(line_index, 0, reason).in_file(path)
+}
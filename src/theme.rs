@@ -0,0 +1,44 @@
+use crossterm::style::Color;
+
+use std::convert::TryFrom;
+
+//colours used to draw the grid
+#[derive(Clone)]
+pub struct Theme {
+    //alive cells, one colour for each state of multi-colour rules
+    pub colours: [Color; 4],
+    pub dead: Color,
+    //outside of the grid
+    pub outside: Color,
+    pub border: Color,
+}
+impl Default for Theme {
+    fn default() -> Theme {
+        Theme {
+            colours: [Color::DarkCyan, Color::DarkYellow, Color::Red, Color::Green],
+            dead: Color::Magenta,
+            outside: Color::DarkRed,
+            border: Color::DarkGreen,
+        }
+    }
+}
+impl Theme {
+    pub fn colour(&self, c: u8) -> Color {
+        self.colours.get(c as usize).cloned().unwrap_or(self.colours[0])
+    }
+}
+
+//names like "dark_cyan", or "#rrggbb"
+pub fn parse_colour(s: &str) -> Result<Color, String> {
+    if let Some(hex) = s.strip_prefix('#') {
+        if hex.len() == 6 && hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            let n = u32::from_str_radix(hex, 16).unwrap();
+            return Ok(Color::Rgb {
+                r: (n >> 16) as u8,
+                g: (n >> 8) as u8,
+                b: n as u8,
+            });
+        }
+    }
+    Color::try_from(s).map_err(|_| format!("unknown colour '{}'", s))
+}
@@ -0,0 +1,151 @@
+use crate::error::Error;
+use crate::rule::Lattice;
+use crate::transform::Cell;
+use crate::{GameGrid, GameOfLife};
+
+use std::collections::HashSet;
+use std::str::FromStr;
+
+//shape of the universe the pattern lives in
+#[derive(Clone, Copy, PartialEq)]
+pub enum Topology {
+    //unbounded, the grid grows with the pattern
+    Plane,
+    //width x height cells, the cells leaving by one edge come back by the opposite one
+    Torus(isize, isize),
+}
+
+//"plane" or "torus:<width>x<height>"
+impl FromStr for Topology {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Topology, ()> {
+        let (name, value) = s.split_once(':').unwrap_or((s, ""));
+        match (name, value) {
+            ("plane", "") => Ok(Topology::Plane),
+            ("torus", v) => {
+                let (w, h) = v.split_once('x').ok_or(())?;
+                let w = w.trim().parse().map_err(|_| ())?;
+                let h = h.trim().parse().map_err(|_| ())?;
+                if w < 1 || h < 1 {
+                    return Err(());
+                }
+                Ok(Topology::Torus(w, h))
+            }
+            _ => Err(()),
+        }
+    }
+}
+
+impl GameGrid {
+    //alive cells outside of the torus are moved to the same place inside of it
+    fn wrap(&mut self, width: isize, height: isize) {
+        let outside: Vec<Cell> = self
+            .alive_cells_with_colour()
+            .into_iter()
+            .filter(|(x, y, _)| !(0..width).contains(x) || !(0..height).contains(y))
+            .collect();
+        for (x, y, _) in outside.iter() {
+            self.set_cell(*x, *y, false);
+        }
+        let wrapped: Vec<Cell> = outside
+            .iter()
+            .map(|(x, y, c)| (x.rem_euclid(width), y.rem_euclid(height), *c))
+            .collect();
+        self.set_cells(&wrapped);
+    }
+
+    //copies of the cells at less than radius cells from an edge are put beyond the opposite
+    //one, so that the cells of both edges are neighbours
+    fn add_margin(&mut self, width: isize, height: isize, radius: isize) {
+        let inside = |v: isize, len: isize| -radius <= v && v < len + radius;
+        let (nx, ny) = (radius / width + 1, radius / height + 1);
+        let mut copies = vec![];
+        for (x, y, c) in self.alive_cells_with_colour() {
+            for i in -nx..=nx {
+                for j in -ny..=ny {
+                    let (cx, cy) = (x + i * width, y + j * height);
+                    if (i, j) != (0, 0) && inside(cx, width) && inside(cy, height) {
+                        copies.push((cx, cy, c));
+                    }
+                }
+            }
+        }
+        self.set_cells(&copies);
+    }
+
+    //cells outside of the torus die
+    fn clip(&mut self, width: isize, height: isize) {
+        let outside: Vec<(isize, isize)> = self
+            .alive_cells
+            .coords
+            .iter()
+            .filter(|(x, y)| !(0..width).contains(x) || !(0..height).contains(y))
+            .cloned()
+            .collect();
+        for (x, y) in outside {
+            self.set_cell(x, y, false);
+        }
+    }
+}
+
+impl GameOfLife {
+    //the pattern is wrapped on the torus right away
+    pub fn set_topology(&mut self, topology: Topology) -> Result<(), Error> {
+        if let Topology::Torus(width, height) = topology {
+            if self.rule.elementary_number().is_some() {
+                return Err(Error::invalid(
+                    "one dimensional automata can't be run on a torus",
+                ));
+            }
+            //the lattices and the blocks of Margolus rules alternate every other cell
+            let alternates =
+                self.rule.lattice != Lattice::Square || self.rule.margolus_table(0).is_some();
+            if alternates && (width % 2 != 0 || height % 2 != 0) {
+                return Err(Error::Invalid(format!(
+                    "the torus must have an even width and height with the rule {}",
+                    self.rule.name
+                )));
+            }
+            let radius = self.rule.radius();
+            self.game_grid.wrap(width, height);
+            self.game_grid.update_edges(radius);
+            if let Some(previous) = self.previous.as_mut() {
+                previous.wrap(width, height);
+                previous.update_edges(radius);
+            }
+        }
+        self.topology = topology;
+        Ok(())
+    }
+
+    //one generation on the plane with the margins, which are removed afterwards
+    pub fn next_on_torus(&mut self, width: isize, height: isize) -> bool {
+        //cells moved out by the editor or the transformations
+        self.game_grid.wrap(width, height);
+        if let Some(previous) = self.previous.as_mut() {
+            previous.wrap(width, height);
+        }
+        let before: HashSet<(isize, isize)> = self.game_grid.alive_cells.coords.clone();
+        //the second-order mode goes on while the previous generation differs
+        let previous_changed = match &self.previous {
+            Some(previous) => previous.alive_cells.coords != before,
+            None => false,
+        };
+        let radius = self.rule.radius().max(1);
+        self.game_grid.add_margin(width, height, radius);
+
+        self.topology = Topology::Plane;
+        self.next();
+        self.topology = Topology::Torus(width, height);
+
+        self.game_grid.clip(width, height);
+        self.game_grid.update_edges(self.rule.radius());
+        if let Some(previous) = self.previous.as_mut() {
+            previous.clip(width, height);
+            previous.update_edges(self.rule.radius());
+        }
+        //changes of the margins don't count
+        previous_changed || before != self.game_grid.alive_cells.coords
+    }
+}
@@ -0,0 +1,184 @@
+use crate::error::Error;
+use crate::rule::Lattice;
+use crate::{GameGrid, GameOfLife};
+
+use std::str::FromStr;
+
+//alive cell with its colour
+pub type Cell = (isize, isize, u8);
+
+//cells of the bounding box of a pattern, the grid stores all of them
+pub const MAX_GRID_CELLS: usize = 4_000_000;
+
+#[derive(Clone, Copy)]
+pub enum Transform {
+    //quarter turns clockwise
+    Rotate(u32),
+    FlipHorizontal,
+    FlipVertical,
+    Transpose,
+    Translate(isize, isize),
+    //every cell becomes a square of n x n cells
+    Scale(usize),
+}
+
+//"rotate90", "rotate180", "rotate270", "flip-h", "flip-v", "transpose", "translate:<dx>,<dy>"
+//and "scale:<n>"
+impl FromStr for Transform {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Transform, ()> {
+        let (name, value) = s.split_once(':').unwrap_or((s, ""));
+        match (name, value) {
+            ("rotate90", "") => Ok(Transform::Rotate(1)),
+            ("rotate180", "") => Ok(Transform::Rotate(2)),
+            ("rotate270", "") => Ok(Transform::Rotate(3)),
+            ("flip-h", "") => Ok(Transform::FlipHorizontal),
+            ("flip-v", "") => Ok(Transform::FlipVertical),
+            ("transpose", "") => Ok(Transform::Transpose),
+            ("translate", v) => {
+                let (dx, dy) = v.split_once(',').ok_or(())?;
+                let dx = dx.trim().parse().map_err(|_| ())?;
+                let dy = dy.trim().parse().map_err(|_| ())?;
+                Ok(Transform::Translate(dx, dy))
+            }
+            ("scale", v) => match v.parse() {
+                Ok(n) if n > 0 => Ok(Transform::Scale(n)),
+                _ => Err(()),
+            },
+            _ => Err(()),
+        }
+    }
+}
+
+impl Transform {
+    //the top left corner of the bounding box (min x, min y, max x, max y) stays in place, except
+    //for translations. The box can be the one of a larger set of cells
+    pub fn apply(&self, cells: &[Cell], bounds: (isize, isize, isize, isize)) -> Vec<Cell> {
+        let (min_x, min_y, max_x, max_y) = bounds;
+        let (w, h) = (max_x - min_x, max_y - min_y);
+
+        let mut res = vec![];
+        for (x, y, colour) in cells.iter() {
+            //coordinates in the bounding box
+            let (i, j) = (x - min_x, y - min_y);
+            let moved = match *self {
+                Transform::Rotate(n) => match n % 4 {
+                    1 => vec![(h - j, i)],
+                    2 => vec![(w - i, h - j)],
+                    3 => vec![(j, w - i)],
+                    _ => vec![(i, j)],
+                },
+                Transform::FlipHorizontal => vec![(w - i, j)],
+                Transform::FlipVertical => vec![(i, h - j)],
+                Transform::Transpose => vec![(j, i)],
+                Transform::Translate(dx, dy) => vec![(i + dx, j + dy)],
+                Transform::Scale(n) => {
+                    let n = n as isize;
+                    let mut block = vec![];
+                    for a in 0..n {
+                        for b in 0..n {
+                            block.push((i * n + a, j * n + b));
+                        }
+                    }
+                    block
+                }
+            };
+            for (i, j) in moved {
+                res.push((min_x + i, min_y + j, *colour));
+            }
+        }
+        res
+    }
+}
+
+//patterns larger than this would take too much memory
+pub fn check_size(width: usize, height: usize) -> Result<(), Error> {
+    match width.checked_mul(height) {
+        Some(cells) if cells <= MAX_GRID_CELLS => Ok(()),
+        _ => Err(Error::invalid("the pattern would be too large")),
+    }
+}
+
+//the pattern in the bounding box can be transformed without growing too large. The grid holds
+//the cells before and after the transformation until its edges are updated
+pub fn check_transform(t: Transform, bounds: (isize, isize, isize, isize)) -> Result<(), Error> {
+    let (min_x, min_y, max_x, max_y) = bounds;
+    let (w, h) = ((max_x - min_x + 1) as usize, (max_y - min_y + 1) as usize);
+    let (w, h) = match t {
+        Transform::Scale(n) => (w.saturating_mul(n), h.saturating_mul(n)),
+        Transform::Translate(dx, dy) => (
+            w.saturating_add(dx.unsigned_abs()),
+            h.saturating_add(dy.unsigned_abs()),
+        ),
+        Transform::Rotate(n) if n % 2 == 1 => (w.max(h), w.max(h)),
+        Transform::Transpose => (w.max(h), w.max(h)),
+        _ => (w, h),
+    };
+    check_size(w, h)
+}
+
+pub fn bounds(cells: &[Cell]) -> Option<(isize, isize, isize, isize)> {
+    if cells.is_empty() {
+        return None;
+    }
+    Some((
+        cells.iter().map(|c| c.0).min().unwrap(),
+        cells.iter().map(|c| c.1).min().unwrap(),
+        cells.iter().map(|c| c.0).max().unwrap(),
+        cells.iter().map(|c| c.1).max().unwrap(),
+    ))
+}
+
+impl GameGrid {
+    pub fn alive_cells_with_colour(&mut self) -> Vec<Cell> {
+        let mut coords: Vec<(isize, isize)> = self.alive_cells.coords.iter().cloned().collect();
+        coords.sort();
+        coords
+            .into_iter()
+            .map(|(x, y)| (x, y, self.get_cell(x, y).unwrap().colour))
+            .collect()
+    }
+
+    pub fn set_cells(&mut self, cells: &[Cell]) {
+        for (x, y, colour) in cells.iter() {
+            self.set_cell(*x, *y, true);
+            self.get_cell(*x, *y).unwrap().colour = *colour;
+        }
+    }
+
+    fn transform(&mut self, t: Transform, bounds: (isize, isize, isize, isize), radius: isize) {
+        let cells = t.apply(&self.alive_cells_with_colour(), bounds);
+        self.clear();
+        self.set_cells(&cells);
+        self.update_edges(radius);
+    }
+}
+
+impl GameOfLife {
+    //the previous generation of the second-order mode is moved with the current one
+    pub fn transform(&mut self, t: Transform) -> Result<(), Error> {
+        if self.rule.lattice != Lattice::Square || self.rule.elementary_number().is_some() {
+            return Err(Error::invalid(
+                "patterns can only be transformed on two dimensional square grids",
+            ));
+        }
+        //both generations are transformed in the same bounding box so that they stay aligned
+        let mut cells = self.game_grid.alive_cells_with_colour();
+        if let Some(previous) = self.previous.as_mut() {
+            cells.append(&mut previous.alive_cells_with_colour());
+        }
+        let bounds = match bounds(&cells) {
+            Some(b) => b,
+            None => return Ok(()),
+        };
+        check_transform(t, bounds)?;
+
+        let radius = self.rule.radius();
+        self.game_grid.transform(t, bounds, radius);
+        if let Some(previous) = self.previous.as_mut() {
+            previous.transform(t, bounds, radius);
+        }
+        Ok(())
+    }
+}
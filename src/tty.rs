@@ -0,0 +1,71 @@
+use crate::error::Error;
+
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyModifiers};
+use crossterm::terminal::{
+    disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen,
+};
+use crossterm::{cursor, execute};
+
+use signal_hook::consts::{SIGINT, SIGTERM};
+use signal_hook::iterator::Signals;
+
+use std::io::stdout;
+use std::panic;
+use std::process;
+use std::thread;
+
+//raw mode on the alternate screen, so that the user's scrollback is kept. The terminal is put back
+//as it was when the guard is dropped, when the program panics and when it gets SIGINT or SIGTERM
+pub struct TerminalGuard;
+
+impl TerminalGuard {
+    pub fn new() -> Result<TerminalGuard, Error> {
+        enable_raw_mode().map_err(Error::Terminal)?;
+        //from here on the terminal is restored by the guard, even if the rest fails
+        let guard = TerminalGuard;
+        execute!(stdout(), EnterAlternateScreen).map_err(Error::Terminal)?;
+
+        //the message of the panic is printed once the terminal is back to normal
+        let hook = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            restore();
+            hook(info);
+        }));
+
+        let mut signals = Signals::new([SIGINT, SIGTERM])?;
+        thread::spawn(move || {
+            if let Some(signal) = signals.forever().next() {
+                restore();
+                process::exit(128 + signal);
+            }
+        });
+        Ok(guard)
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        restore();
+    }
+}
+
+//errors are ignored, there is nothing left to do with the terminal
+#[allow(unused_must_use)]
+fn restore() {
+    execute!(stdout(), LeaveAlternateScreen, cursor::Show);
+    disable_raw_mode();
+}
+
+//next event of the terminal. Raw mode turns ctrl+c into a key, which is given back its usual
+//meaning by sending SIGINT to the program
+pub fn read_event() -> Result<Event, Error> {
+    let e = event::read().map_err(Error::Terminal)?;
+    if let Event::Key(KeyEvent {
+        code: KeyCode::Char('c'),
+        modifiers: KeyModifiers::CONTROL,
+    }) = e
+    {
+        signal_hook::low_level::raise(SIGINT)?;
+    }
+    Ok(e)
+}
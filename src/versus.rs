@@ -0,0 +1,261 @@
+use crate::error::Error;
+use crate::rule::Rule;
+use crate::tty::read_event;
+use crate::theme::Theme;
+use crate::{GameGrid, GameOfLife};
+
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyModifiers};
+use crossterm::style::{self, Print, Stylize};
+use crossterm::terminal::{Clear, ClearType};
+use crossterm::{cursor, execute, queue};
+
+use std::cmp::Ordering;
+use std::io::Stdout;
+use std::thread;
+use std::time::Duration;
+
+//size of the board, the cells outside of it stay dead
+const WIDTH: isize = 40;
+const HEIGHT: isize = 18;
+//cells each player can place during the setup
+const CELLS_PER_PLAYER: usize = 20;
+const GENERATIONS: isize = 100;
+//generations per second once the setup is over
+const SPEED: u64 = 8;
+
+//two players take turns placing cells of their colour in their half of the board, then the
+//Immigration rule runs and the player with the most cells wins
+struct Match {
+    game: GameOfLife,
+    //player placing cells, None once the generations run
+    turn: Option<u8>,
+    cursor: (isize, isize),
+    placed: [usize; 2],
+}
+
+impl Match {
+    fn new() -> Match {
+        let mut g = GameGrid::new();
+        //grows the grid to the size of the board
+        g.set_cell(WIDTH - 1, HEIGHT - 1, false);
+        Match {
+            game: GameOfLife::new(g, Rule::parse("Immigration").unwrap()),
+            turn: None,
+            cursor: (0, 0),
+            placed: [0, 0],
+        }
+    }
+
+    //first and last columns where the player can place cells, player 1 has the left half
+    fn half(player: u8) -> (isize, isize) {
+        match player {
+            0 => (0, WIDTH / 2 - 1),
+            _ => (WIDTH / 2, WIDTH - 1),
+        }
+    }
+
+    fn start_turn(&mut self, player: u8) {
+        let (min, max) = Match::half(player);
+        self.turn = Some(player);
+        self.cursor = ((min + max) / 2, HEIGHT / 2);
+    }
+
+    fn move_cursor(&mut self, player: u8, dx: isize, dy: isize) {
+        let (min, max) = Match::half(player);
+        self.cursor.0 = (self.cursor.0 + dx).clamp(min, max);
+        self.cursor.1 = (self.cursor.1 + dy).clamp(0, HEIGHT - 1);
+    }
+
+    //add or remove a cell of the player under the cursor, only the player's cells can be in
+    //the player's half
+    fn toggle(&mut self, player: u8) {
+        let (x, y) = self.cursor;
+        let grid = &mut self.game.game_grid;
+        if grid.is_alive(x, y) {
+            grid.set_cell(x, y, false);
+            self.placed[player as usize] -= 1;
+        } else if self.placed[player as usize] < CELLS_PER_PLAYER {
+            grid.set_cell(x, y, true);
+            grid.get_cell(x, y).unwrap().colour = player;
+            self.placed[player as usize] += 1;
+        }
+    }
+
+    //cells born outside of the board die right away
+    fn next(&mut self) -> bool {
+        let changed = self.game.next();
+        let grid = &mut self.game.game_grid;
+        let outside: Vec<(isize, isize)> = grid
+            .alive_cells
+            .coords
+            .iter()
+            .filter(|(x, y)| !(0..WIDTH).contains(x) || !(0..HEIGHT).contains(y))
+            .cloned()
+            .collect();
+        for (x, y) in outside.iter() {
+            grid.set_cell(*x, *y, false);
+        }
+        grid.update_edges(self.game.rule.radius());
+        changed
+    }
+
+    //number of alive cells of each player
+    fn scores(&mut self) -> [usize; 2] {
+        let mut res = [0, 0];
+        let grid = &mut self.game.game_grid;
+        let alive: Vec<(isize, isize)> = grid.alive_cells.coords.iter().cloned().collect();
+        for (x, y) in alive {
+            res[grid.get_cell(x, y).unwrap().colour as usize % 2] += 1;
+        }
+        res
+    }
+
+    //replaces the hud during a match
+    #[allow(unused_must_use)]
+    fn scoreboard(&mut self, so: &mut Stdout) {
+        match self.turn {
+            Some(p) => {
+                queue!(
+                    so,
+                    Print(format!("Player {}: arrows to move; space to place or remove a cell; enter to end your turn; 'q' to quit", p + 1)),
+                    cursor::MoveToNextLine(1),
+                    Print(format!(
+                        "Cells left: {}/{}; the other player's cells are hidden",
+                        CELLS_PER_PLAYER - self.placed[p as usize],
+                        CELLS_PER_PLAYER
+                    ))
+                );
+            }
+            None => {
+                let scores = self.scores();
+                queue!(
+                    so,
+                    Print(format!(
+                        "'q' to quit; Generation: {}/{}",
+                        self.game.generation, GENERATIONS
+                    )),
+                    cursor::MoveToNextLine(1)
+                );
+                for (p, s) in scores.iter().enumerate() {
+                    let text = format!("Player {}: {} cells  ", p + 1, s);
+                    queue!(so, style::PrintStyledContent(text.with(self.game.theme.colour(p as u8))));
+                }
+            }
+        }
+    }
+
+    #[allow(unused_must_use)]
+    fn show(&mut self, so: &mut Stdout) {
+        let border_style = style::PrintStyledContent("█".with(self.game.theme.border));
+        queue!(so, Clear(ClearType::All), cursor::MoveTo(0, 0));
+        self.scoreboard(so);
+
+        queue!(so, cursor::MoveToNextLine(1));
+        for _ in 0..WIDTH + 2 {
+            queue!(so, &border_style);
+        }
+        for y in 0..HEIGHT {
+            queue!(so, cursor::MoveToNextLine(1), &border_style);
+            for x in 0..WIDTH {
+                let alive = self.game.game_grid.is_alive(x, y);
+                let c = match self.game.game_grid.get_cell(x, y) {
+                    Some(c) => c.colour,
+                    None => 0,
+                };
+                match self.turn {
+                    Some(p) if (x, y) == self.cursor => {
+                        queue!(so, style::PrintStyledContent("X".with(self.game.theme.colour(p))))
+                    }
+                    //the other half is hidden during the setup
+                    Some(p) if !(Match::half(p).0..=Match::half(p).1).contains(&x) => {
+                        queue!(so, style::PrintStyledContent("-".with(self.game.theme.outside)))
+                    }
+                    _ if alive => queue!(so, style::PrintStyledContent("█".with(self.game.theme.colour(c)))),
+                    _ => queue!(so, style::PrintStyledContent("+".with(self.game.theme.dead))),
+                };
+            }
+            queue!(so, &border_style);
+        }
+        queue!(so, cursor::MoveToNextLine(1));
+        for _ in 0..WIDTH + 2 {
+            queue!(so, &border_style);
+        }
+        execute!(so);
+    }
+}
+
+//hot-seat match, back to the menu when it's over
+#[allow(unused_must_use)]
+pub fn play(so: &mut Stdout, theme: &Theme) -> Result<(), Error> {
+    let mut m = Match::new();
+    m.game.theme = theme.clone();
+
+    for player in 0..2 {
+        m.start_turn(player);
+        loop {
+            m.show(so);
+            match read_event()? {
+                Event::Key(KeyEvent {
+                    code: KeyCode::Up,
+                    modifiers: KeyModifiers::NONE,
+                }) => m.move_cursor(player, 0, -1),
+                Event::Key(KeyEvent {
+                    code: KeyCode::Down,
+                    modifiers: KeyModifiers::NONE,
+                }) => m.move_cursor(player, 0, 1),
+                Event::Key(KeyEvent {
+                    code: KeyCode::Left,
+                    modifiers: KeyModifiers::NONE,
+                }) => m.move_cursor(player, -1, 0),
+                Event::Key(KeyEvent {
+                    code: KeyCode::Right,
+                    modifiers: KeyModifiers::NONE,
+                }) => m.move_cursor(player, 1, 0),
+                Event::Key(KeyEvent {
+                    code: KeyCode::Char(' '),
+                    modifiers: KeyModifiers::NONE,
+                }) => m.toggle(player),
+                Event::Key(KeyEvent {
+                    code: KeyCode::Enter,
+                    modifiers: KeyModifiers::NONE,
+                }) => break,
+                Event::Key(KeyEvent {
+                    code: KeyCode::Char('q'),
+                    modifiers: KeyModifiers::NONE,
+                }) => return Ok(()),
+                _ => (),
+            }
+        }
+    }
+
+    m.turn = None;
+    m.game.game_grid.update_edges(m.game.rule.radius());
+    m.show(so);
+    while m.game.generation < GENERATIONS && m.next() {
+        thread::sleep(Duration::from_millis(1000 / SPEED));
+        while event::poll(Duration::from_millis(1)).map_err(Error::Terminal)? {
+            if let Event::Key(KeyEvent {
+                code: KeyCode::Char('q'),
+                modifiers: KeyModifiers::NONE,
+            }) = read_event()?
+            {
+                return Ok(());
+            }
+        }
+        m.show(so);
+    }
+
+    let scores = m.scores();
+    let result = match scores[0].cmp(&scores[1]) {
+        Ordering::Greater => "Player 1 wins",
+        Ordering::Less => "Player 2 wins",
+        Ordering::Equal => "Draw",
+    };
+    execute!(
+        so,
+        cursor::MoveToNextLine(1),
+        Print(format!("{}! Press any key to go back to the menu", result))
+    );
+    read_event()?;
+    Ok(())
+}